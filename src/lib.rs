@@ -1,6 +1,10 @@
 pub use crate::simulate::{
-    AssetChecker, AssetType, Call, ERC20Checker, ForkInfo, MissingAssetInfo, PotentialMissingAsset,
-    asset_simulator::AssetSimulator,
+    AccountOverride, AllowanceChecker, AssetChecker, AssetGrant, AssetType, AuthorizationFailure,
+    BlockerCategory, BundleReport, Call, CheckOptions, DealStrategy, DiscoveryMode,
+    DiscoveryReport, DiscoveryStatus, ERC20Checker, ERC721Checker, ERC1155Checker, ForkInfo,
+    MissingAssetInfo, NativeChecker, PermitFailure, PotentialMissingAsset, RequirementKind,
+    StateOverride, StepRequirement, TimeRequirement, asset_simulator::AssetSimulator,
+    parse_state_override,
 };
 
 pub mod simulate;
@@ -1,15 +1,34 @@
+use crate::simulate::blocker;
 use crate::simulate::builder::AssetSimulatorBuilder;
 use crate::simulate::checkers::AssetChecker;
-use crate::simulate::types::{AssetContext, AssetSpec, Call, MissingAssetInfo};
-use crate::simulate::utils::find_last_non_proxy_call;
-use forge::executors::Executor;
-use forge::revm::primitives::{Address, U256};
+use crate::simulate::checkers::native::upfront_cost;
+use crate::simulate::error::AssetSimulatorError;
+use crate::simulate::ledger::{Holding, Ledger, Outflow};
+use crate::simulate::overrides::apply_state_override;
+use crate::simulate::reconcile::{attribute, has_new_requirements, reconcile};
+use crate::simulate::report::{BundleReport, DiscoveryReport, DiscoveryStatus, Iteration};
+use crate::simulate::time_warp::{self, TimeRequirement};
+use crate::simulate::types::{
+    AssetContext, AssetGrant, AssetSpec, Call, CheckOptions, DealStrategy, DiscoveryMode,
+    MissingAssetInfo, RequirementKind, TraceFrame,
+};
+use crate::simulate::utils::{candidate_frames, in_constructor_of};
+use forge::backend::{DatabaseExt, RevertStateSnapshotAction};
+use forge::executors::{Executor, RawCallResult};
+use forge::revm::JournaledState;
+use forge::revm::primitives::{Address, AuthorizationList, Env, EnvWithHandlerCfg, TxKind, U256};
+use forge::traces::{CallTraceNode, SparsedTraceArena};
+use foundry_evm_core::decode::RevertDecoder;
 use log::{error, info};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
 // Main simulator that orchestrates simulation and checking
 pub struct AssetSimulator {
     executor: Executor,
-    checkers: Vec<Box<dyn AssetChecker>>,
+    checkers: Arc<Vec<Box<dyn AssetChecker>>>, // Shared with branches
+    accounts_of_interest: HashSet<Address>,
+    snapshots: HashMap<String, U256>, // Name → backend snapshot id
 }
 
 impl AssetSimulator {
@@ -19,8 +38,17 @@ impl AssetSimulator {
     }
 
     /// Internal helper used only by the builder.
-    pub(crate) fn new_from_parts(executor: Executor, checkers: Vec<Box<dyn AssetChecker>>) -> Self {
-        Self { executor, checkers }
+    pub(crate) fn new_from_parts(
+        executor: Executor,
+        checkers: Vec<Box<dyn AssetChecker>>,
+        accounts_of_interest: HashSet<Address>,
+    ) -> Self {
+        Self {
+            executor,
+            checkers: Arc::new(checkers),
+            accounts_of_interest,
+            snapshots: HashMap::new(),
+        }
     }
 
     /// Mutable access for advanced helpers/tests.
@@ -29,125 +57,1210 @@ impl AssetSimulator {
     }
 
     // ========================================================================
-    //  TRANSACTION CHECKING
+    //  ACCOUNTS OF INTEREST
+    // ========================================================================
+
+    /// Accounts whose shortfalls are reported and dealt. Empty means "all".
+    pub fn accounts_of_interest(&self) -> &HashSet<Address> {
+        &self.accounts_of_interest
+    }
+
+    /// Replace the accounts of interest. Shortfalls of any other account are
+    /// reported as external blockers and never dealt.
+    pub fn set_accounts_of_interest(&mut self, accounts: impl IntoIterator<Item = Address>) {
+        self.accounts_of_interest = accounts.into_iter().collect();
+    }
+
+    /// `true` if no filter is configured or `account` is part of it.
+    pub fn is_account_of_interest(&self, account: Address) -> bool {
+        self.accounts_of_interest.is_empty() || self.accounts_of_interest.contains(&account)
+    }
+
+    // ========================================================================
+    //  SNAPSHOTS
+    // ========================================================================
+
+    /// Save the current state as `name`, replacing any snapshot of that name.
+    pub fn snapshot(&mut self, name: impl Into<String>) {
+        let id = self.snapshot_state();
+        if let Some(replaced) = self.snapshots.insert(name.into(), id) {
+            self.executor.backend_mut().delete_state_snapshot(replaced);
+        }
+    }
+
+    /// Go back to the state saved as `name`. The snapshot is kept, so this
+    /// works any number of times.
+    pub fn revert_to(&mut self, name: &str) -> Result<(), eyre::Error> {
+        let id = self.snapshot_id(name)?;
+        if !self.restore_state(id, RevertStateSnapshotAction::RevertKeep) {
+            return Err(AssetSimulatorError::UnknownSnapshot(name.to_string()).into());
+        }
+        Ok(())
+    }
+
+    /// Forget the snapshot saved as `name`. Returns `false` if there was none.
+    pub fn delete_snapshot(&mut self, name: &str) -> bool {
+        match self.snapshots.remove(name) {
+            Some(id) => self.executor.backend_mut().delete_state_snapshot(id),
+            None => false,
+        }
+    }
+
+    /// Names of the saved snapshots, in no particular order.
+    pub fn snapshots(&self) -> impl Iterator<Item = &str> {
+        self.snapshots.keys().map(String::as_str)
+    }
+
+    /// A separate simulator starting from the state saved as `name`, e.g. to
+    /// try another payment asset. It shares the fork (and its cache) and the
+    /// checkers with this one, so nothing is fetched or set up again.
+    pub fn branch(&self, name: &str) -> Result<Self, eyre::Error> {
+        let id = self.snapshot_id(name)?;
+        let mut branch = Self {
+            executor: self.executor.clone(),
+            checkers: Arc::clone(&self.checkers),
+            accounts_of_interest: self.accounts_of_interest.clone(),
+            snapshots: self.snapshots.clone(),
+        };
+        if !branch.restore_state(id, RevertStateSnapshotAction::RevertKeep) {
+            return Err(AssetSimulatorError::UnknownSnapshot(name.to_string()).into());
+        }
+        Ok(branch)
+    }
+
+    fn snapshot_id(&self, name: &str) -> Result<U256, eyre::Error> {
+        self.snapshots
+            .get(name)
+            .copied()
+            .ok_or_else(|| AssetSimulatorError::UnknownSnapshot(name.to_string()).into())
+    }
+
     // ========================================================================
-    pub async fn check_transaction(
+    //  GRANTS
+    // ========================================================================
+
+    /// Give each recipient its granted asset through the matching checker's
+    /// `deal`, e.g. "assume alice has 5 WETH and NFT #12". Grants persist
+    /// across checks, so later discoveries only report what *else* is needed.
+    /// Like every deal, a grant never lowers what the recipient holds.
+    pub fn apply_grants(&mut self, grants: Vec<AssetGrant>) -> Result<(), eyre::Error> {
+        for grant in grants {
+            let asset_type = grant.asset_type();
+            let checker = self
+                .checkers
+                .iter()
+                .find(|checker| checker.asset_type() == asset_type && checker.deals_holdings())
+                .ok_or_else(|| {
+                    eyre::eyre!(
+                        "no checker can deal {:?} assets; add one to the builder",
+                        asset_type
+                    )
+                })?;
+
+            info!("Granting {:?} to {:?}", grant.asset, grant.recipient);
+            let context = AssetContext::for_grant(&grant);
+            checker.deal(grant.recipient, grant.asset, &mut self.executor, &context)?;
+        }
+        Ok(())
+    }
+
+    /// Whether `grants` alone make `call` succeed. Nothing else is dealt and
+    /// the grants are reverted afterwards.
+    pub async fn verify_with_grants(
         &mut self,
         call: Call,
-    ) -> Result<Vec<MissingAssetInfo>, eyre::Error> {
-        self.check_transaction_with_options(call, true, 10).await
+        grants: Vec<AssetGrant>,
+    ) -> Result<bool, eyre::Error> {
+        let snapshot = self.snapshot_state();
+        let verified = self
+            .apply_grants(grants)
+            .map(|()| passes(&self.executor, &call));
+        self.revert_state(snapshot);
+        verified
+    }
+
+    // ========================================================================
+    //  TRANSACTION CHECKING
+    // ========================================================================
+    pub async fn check_transaction(&mut self, call: Call) -> Result<DiscoveryReport, eyre::Error> {
+        self.check_transaction_with_options(call, CheckOptions::default())
+            .await
     }
 
+    /// Every check runs inside a backend snapshot that is reverted once it's
+    /// done, so deals never leak into the next query. Set
+    /// `CheckOptions::keep_state` to keep them.
     pub async fn check_transaction_with_options(
         &mut self,
         call: Call,
-        auto_fix: bool,
-        max_iterations: usize,
-    ) -> Result<Vec<MissingAssetInfo>, eyre::Error> {
-        let mut all_missing_assets = Vec::new();
+        options: CheckOptions,
+    ) -> Result<DiscoveryReport, eyre::Error> {
+        let snapshot = (!options.keep_state).then(|| self.snapshot_state());
 
-        for _iteration in 0..max_iterations {
+        let report = apply_state_override(&options.state_override, &mut self.executor)
+            .and_then(|()| self.check(call, &options));
+
+        if let Some(snapshot) = snapshot {
+            self.revert_state(snapshot);
+        }
+        report
+    }
+
+    fn check(
+        &mut self,
+        call: Call,
+        options: &CheckOptions,
+    ) -> Result<DiscoveryReport, eyre::Error> {
+        let report = self.discover(call.clone(), options)?.report;
+        if !options.time_warp || report.status != DiscoveryStatus::Reverted {
+            return Ok(report);
+        }
+        // Nothing left to deal; the revert may be about time instead
+        match time_warp::search(&self.executor, &call) {
+            Some(requirement) => self.discover_warped(call, options, requirement, report),
+            None => Ok(report),
+        }
+    }
+
+    /// Discover again in the block that gets past a time gate, on top of
+    /// what the first pass dealt. Both passes end up in one report, unless
+    /// the warped run gets no further than the first one did.
+    fn discover_warped(
+        &mut self,
+        call: Call,
+        options: &CheckOptions,
+        requirement: TimeRequirement,
+        first: DiscoveryReport,
+    ) -> Result<DiscoveryReport, eyre::Error> {
+        info!("Re-running past the time gate: {:?}", requirement);
+        // Kept to drop the warped pass's deals along with its report
+        let before = self.executor.clone();
+        let block = self.executor.env().block.clone();
+        requirement.apply(self.executor.env_mut());
+        let warped = self.discover(call, options);
+        self.executor.env_mut().block = block;
+        let warped = warped?.report;
+
+        // Failing differently isn't getting past the gate; the warped run
+        // has to succeed or run into something the first pass didn't
+        if !warped.succeeded() && !has_new_requirements(&first.requirements, &warped.requirements) {
+            info!("Warped run gets no further; dropping it");
+            self.executor = before;
+            return Ok(first);
+        }
+
+        let mut iterations = first.iterations;
+        iterations.extend(warped.iterations);
+        let mut requirements = first.requirements;
+        requirements.extend(warped.requirements);
+        Ok(DiscoveryReport {
+            iterations,
+            requirements: reconcile(requirements, None),
+            time_requirement: Some(requirement),
+            ..warped
+        })
+    }
+
+    fn discover(&mut self, call: Call, options: &CheckOptions) -> Result<Discovery, eyre::Error> {
+        let mut discovery = match options.mode {
+            DiscoveryMode::Revert => self.discover_from_reverts(call.clone(), options)?,
+            DiscoveryMode::Outflow => {
+                self.discover_from_outflows(call.clone(), options.max_iterations)?
+            }
+        };
+        // While something fundable is still open, funding it may be all the
+        // tx needs; only a revert with nothing left to fund is a blocker
+        if discovery.report.status == DiscoveryStatus::Reverted
+            && !has_unfunded_requirements(&discovery.report)
+        {
+            let blocker = blocker::classify(&self.executor, &call, &discovery.report);
+            info!("Still failing, classified as {:?}", blocker);
+            discovery.report.blocker = Some(blocker);
+        }
+        Ok(discovery)
+    }
+
+    // ========================================================================
+    //  BUNDLE CHECKING
+    // ========================================================================
+    pub async fn check_bundle(&mut self, calls: Vec<Call>) -> Result<BundleReport, eyre::Error> {
+        self.check_bundle_with_options(calls, CheckOptions::default())
+            .await
+    }
+
+    /// Discover each call in order on shared state: every step sees what
+    /// the previous ones did and dealt. Stops at the first step that still
+    /// fails, since later steps would run on the wrong state. The bundle as
+    /// a whole is isolated like a single check.
+    pub async fn check_bundle_with_options(
+        &mut self,
+        calls: Vec<Call>,
+        options: CheckOptions,
+    ) -> Result<BundleReport, eyre::Error> {
+        let snapshot = (!options.keep_state).then(|| self.snapshot_state());
+
+        let report = apply_state_override(&options.state_override, &mut self.executor)
+            .and_then(|()| self.discover_bundle(calls, &options));
+
+        if let Some(snapshot) = snapshot {
+            self.revert_state(snapshot);
+        }
+        report
+    }
+
+    fn discover_bundle(
+        &mut self,
+        calls: Vec<Call>,
+        options: &CheckOptions,
+    ) -> Result<BundleReport, eyre::Error> {
+        // Funding is measured against the state before step one
+        let before = self.executor.clone();
+        let mut ledger = Ledger::default();
+        let mut fees: HashMap<Address, U256> = HashMap::new();
+        let mut deployers = HashMap::new();
+        let mut steps = Vec::new();
+
+        for (index, call) in calls.into_iter().enumerate() {
+            info!("Checking bundle step {}", index);
+            let from = call.from;
+            *fees.entry(from).or_default() += gas_fee(&self.executor, &call);
+            let Discovery { report, traces } = self.discover(call, options)?;
+            if let Some(traces) = &traces {
+                // Frames only mean something within their own step's trace
+                let outflows = Ledger::from_traces(traces).outflows();
+                deployers.extend(Self::constructor_deployers(traces.nodes(), &outflows, from));
+                ledger.record_traces(traces);
+            }
+            let succeeded = report.succeeded();
+            steps.push(report);
+            if !succeeded {
+                break;
+            }
+        }
+
+        // What moved over the whole bundle, plus what isn't a movement
+        // (approvals, allowances, signatures) and whatever a failing step
+        // still asked for.
+        let mut funding =
+            self.measured_requirements(&ledger.outflows(), &fees, &deployers, &before);
+        for step in &steps {
+            funding.extend(step.requirements.iter().cloned().filter(|missing| {
+                !step.succeeded()
+                    || !matches!(
+                        missing.kind,
+                        RequirementKind::Balance | RequirementKind::Ownership
+                    )
+            }));
+        }
+
+        Ok(BundleReport {
+            requirements: attribute(&steps),
+            funding: reconcile(funding, Some(&ledger)),
+            steps,
+        })
+    }
+
+    // --------------------------------------------------------------------
+    //  Revert mode: deal what each failing frame asks for, then re-run
+    // --------------------------------------------------------------------
+    fn discover_from_reverts(
+        &mut self,
+        call: Call,
+        options: &CheckOptions,
+    ) -> Result<Discovery, eyre::Error> {
+        let auto_fix = options.auto_fix;
+        let mut all_missing_assets = Vec::new();
+        let mut dealt = Vec::new();
+        let mut final_traces = None;
+        let mut iterations = Vec::new();
+        let mut status = DiscoveryStatus::MaxIterationsReached;
+        let mut revert_reason = None;
+        let mut gas_used = 0;
+
+        for _iteration in 0..options.max_iterations {
             // Run the simulation
-            let result =
-                self.executor
-                    .transact_raw(call.from, call.to, call.data.clone(), call.value)?;
+            let result = match transact(&mut self.executor, &call) {
+                Ok(result) => result,
+                Err(err) => {
+                    // revm rejects a tx whose sender can't cover gas * price + value
+                    // before executing anything; treat that as a native shortfall.
+                    let Some(mut missing) = Self::upfront_shortfall(&self.executor, &call)? else {
+                        return Err(err);
+                    };
+                    missing.external = !self.is_account_of_interest(missing.account);
+                    all_missing_assets.push(missing.clone());
+
+                    let mut iteration = Iteration {
+                        reverted: true,
+                        ..Default::default()
+                    };
+                    gas_used = 0;
+                    revert_reason = Some(err.to_string());
+
+                    if auto_fix && !missing.external {
+                        info!("Dealing upfront cost for {:?}", missing.account);
+                        let required = missing.current_balance + missing.missing_amount;
+                        self.executor.set_balance(missing.account, required)?;
+                        iteration.deals.push(missing);
+                        iterations.push(iteration);
+                        continue;
+                    }
+                    iterations.push(iteration);
+                    status = DiscoveryStatus::Reverted;
+                    break;
+                }
+            };
+
+            let reverted = result.exit_reason.is_revert();
+            let mut iteration = Iteration {
+                reverted,
+                gas_used: result.gas_used,
+                ..Default::default()
+            };
+            gas_used = result.gas_used;
 
             // Transaction succeeded → done
-            if !result.exit_reason.is_revert() {
+            if !reverted {
+                iterations.push(iteration);
+                status = DiscoveryStatus::Succeeded;
+                revert_reason = None;
+                final_traces = result.traces;
                 break;
             }
+            revert_reason =
+                Some(RevertDecoder::new().decode(&result.result, Some(result.exit_reason)));
 
-            // ── process traces and apply checkers ─────────────────────────────
+            // ── walk the revert path and apply checkers to every frame ────────
             let mut found_any_missing = false;
             if let Some(traces) = result.traces {
-                if let Some(trace) = find_last_non_proxy_call(&traces) {
-                    for checker in &self.checkers {
-                        if let Some(potential_asset) = checker.identify_asset(trace) {
-                            match checker.check_balance(potential_asset.clone(), &mut self.executor)
-                            {
-                                Ok(missing) if missing.missing_amount > U256::ZERO => {
-                                    // Always record what the checker returns; if the
-                                    // same asset shows up again in a later iteration
-                                    // (e.g. higher amount needed) we still want to
-                                    // capture it.
-                                    all_missing_assets.push(missing.clone());
-                                    found_any_missing = true;
-
-                                    if auto_fix {
-                                        let ctx = AssetContext::from_trace(
-                                            potential_asset,
-                                            trace.clone(),
-                                        );
-                                        info!("Dealing asset for {:?}", missing.account);
-                                        checker.deal(
-                                            missing.account,
-                                            missing.required,
-                                            &mut self.executor,
-                                            &ctx,
-                                        )?;
-                                    }
-                                }
-                                Ok(_) => {} // balance fine
-                                Err(e) => error!(
+                // The same shortfall can surface in several frames (e.g. a
+                // wrapper forwarding a transfer); only handle it once per round.
+                let mut seen = HashSet::new();
+                for node in candidate_frames(&traces) {
+                    let trace = &node.trace;
+                    for (index, checker) in self.checkers.iter().enumerate() {
+                        let Some(potential_asset) = checker.identify_asset(trace) else {
+                            continue;
+                        };
+                        let findings = match checker
+                            .check_balances(potential_asset.clone(), &mut self.executor)
+                        {
+                            Ok(findings) => findings,
+                            Err(e) => {
+                                error!(
                                     "{:?} error while checking balance: {}",
                                     checker.asset_type(),
                                     e
-                                ),
+                                );
+                                continue;
+                            }
+                        };
+                        for mut missing in findings {
+                            if missing.missing_amount.is_zero() {
+                                continue; // balance fine
+                            }
+                            if !seen.insert((
+                                missing.account,
+                                missing.kind,
+                                missing.required.clone(),
+                            )) {
+                                continue;
+                            }
+                            missing.frame = Some(TraceFrame::from_node(node));
+                            Self::attribute_constructor(&mut missing, traces.nodes(), node, &call);
+
+                            // Shortfalls of accounts we don't care about are
+                            // reported as external blockers and never dealt.
+                            missing.external = !self.is_account_of_interest(
+                                missing.deployer.unwrap_or(missing.account),
+                            );
+
+                            // Always record what the checker returns; if the
+                            // same asset shows up again in a later iteration
+                            // (e.g. higher amount needed) we still want to
+                            // capture it.
+                            all_missing_assets.push(missing.clone());
+                            if missing.external || !missing.kind.is_fundable() {
+                                continue;
+                            }
+                            found_any_missing = true;
+
+                            if auto_fix {
+                                let ctx = AssetContext::from_trace(
+                                    potential_asset.clone(),
+                                    trace.clone(),
+                                );
+                                info!("Dealing asset for {:?}", missing.account);
+                                checker.deal(
+                                    missing.account,
+                                    deal_target(missing.required.clone(), options.deal_strategy),
+                                    &mut self.executor,
+                                    &ctx,
+                                )?;
+                                iteration.deals.push(missing.clone());
+                                dealt.push(Dealt {
+                                    checker: index,
+                                    missing,
+                                    context: ctx,
+                                });
                             }
                         }
                     }
                 }
             }
+            iterations.push(iteration);
 
             // Either not auto-fixing or nothing missing → we're done
             if auto_fix && found_any_missing {
                 continue; // another simulation round
             }
+            status = DiscoveryStatus::Reverted;
             break; // exit the loop, aggregate once
         }
 
+        if options.deal_strategy == DealStrategy::Exact && !dealt.is_empty() {
+            if status == DiscoveryStatus::Succeeded {
+                // The exact amounts were enough
+                for deal in &mut dealt {
+                    deal.missing.verified_minimum = fungible_amount(&deal.missing.required);
+                }
+            } else if let Some(stage) = self.search_minimums(&call, &mut dealt)? {
+                // Confirm the minimums on the simulator's own state
+                self.executor = stage;
+                let result = transact(&mut self.executor, &call)?;
+                let reverted = result.exit_reason.is_revert();
+                iterations.push(Iteration {
+                    reverted,
+                    gas_used: result.gas_used,
+                    deals: dealt.iter().map(|deal| deal.missing.clone()).collect(),
+                });
+                gas_used = result.gas_used;
+                if reverted {
+                    revert_reason =
+                        Some(RevertDecoder::new().decode(&result.result, Some(result.exit_reason)));
+                } else {
+                    status = DiscoveryStatus::Succeeded;
+                    revert_reason = None;
+                    final_traces = result.traces;
+                }
+            }
+
+            for deal in &dealt {
+                for missing in all_missing_assets.iter_mut().filter(|missing| {
+                    missing.account == deal.missing.account
+                        && missing.kind == deal.missing.kind
+                        && missing.required == deal.missing.required
+                }) {
+                    missing.verified_minimum = deal.missing.verified_minimum;
+                }
+            }
+        }
+
         // Hit max_iterations OR broke out of the loop for any reason
-        Ok(Self::aggregate_missing_assets(all_missing_assets))
+        let report = DiscoveryReport {
+            status,
+            iterations,
+            revert_reason,
+            gas_used,
+            requirements: reconcile(
+                all_missing_assets,
+                final_traces.as_ref().map(Ledger::from_traces).as_ref(),
+            ),
+            outflows: Vec::new(),
+            time_requirement: None,
+            blocker: None,
+        };
+        Ok(Discovery {
+            report,
+            traces: final_traces,
+        })
     }
 
     // --------------------------------------------------------------------
-    //  Helper: merge duplicates (same account & asset)
+    //  Outflow mode: fund everything touched, then measure what moved
     // --------------------------------------------------------------------
-    fn aggregate_missing_assets(assets: Vec<MissingAssetInfo>) -> Vec<MissingAssetInfo> {
-        use std::collections::HashMap;
-
-        let mut map: HashMap<(Address, AssetSpec), MissingAssetInfo> = HashMap::new();
-
-        for a in assets {
-            let key = (a.account, a.required.clone());
-
-            map.entry(key)
-                .and_modify(|existing| {
-                    // accumulate amounts
-                    existing.missing_amount += a.missing_amount;
-                    if let AssetSpec::ERC20 { token, amount } = &mut existing.required {
-                        if let AssetSpec::ERC20 {
-                            amount: add_amt, ..
-                        } = a.required
-                        {
-                            *amount += add_amt;
+    fn discover_from_outflows(
+        &mut self,
+        call: Call,
+        max_iterations: usize,
+    ) -> Result<Discovery, eyre::Error> {
+        // Funded on a throwaway copy, so balances can be measured against
+        // the state before any funding
+        let mut executor = self.executor.clone();
+        let mut findings = Vec::new();
+        let mut approvals = Vec::new();
+        let mut iterations = Vec::new();
+        let mut status = DiscoveryStatus::MaxIterationsReached;
+        let mut revert_reason = None;
+        let mut gas_used = 0;
+        let mut traces = None;
+
+        for _iteration in 0..max_iterations {
+            let result = match transact(&mut executor, &call) {
+                Ok(result) => result,
+                Err(err) => {
+                    let Some(mut missing) = Self::upfront_shortfall(&executor, &call)? else {
+                        return Err(err);
+                    };
+                    missing.external = !self.is_account_of_interest(missing.account);
+                    findings.push(missing.clone());
+
+                    let mut iteration = Iteration {
+                        reverted: true,
+                        ..Default::default()
+                    };
+                    gas_used = 0;
+                    revert_reason = Some(err.to_string());
+                    if !missing.external {
+                        executor.set_balance(missing.account, U256::MAX >> 1)?;
+                        iteration.deals.push(missing);
+                        iterations.push(iteration);
+                        continue;
+                    }
+                    iterations.push(iteration);
+                    status = DiscoveryStatus::Reverted;
+                    break;
+                }
+            };
+
+            let reverted = result.exit_reason.is_revert();
+            let mut iteration = Iteration {
+                reverted,
+                gas_used: result.gas_used,
+                ..Default::default()
+            };
+            gas_used = result.gas_used;
+
+            if !reverted {
+                iterations.push(iteration);
+                status = DiscoveryStatus::Succeeded;
+                revert_reason = None;
+                traces = result.traces;
+                break;
+            }
+            revert_reason =
+                Some(RevertDecoder::new().decode(&result.result, Some(result.exit_reason)));
+
+            // Every frame counts here, not just the revert path: anything the
+            // tx touches gets funded in one go.
+            let mut found_any_missing = false;
+            if let Some(traces) = result.traces {
+                let mut seen = HashSet::new();
+                for node in traces.nodes() {
+                    let trace = &node.trace;
+                    for (index, checker) in self.checkers.iter().enumerate() {
+                        let Some(potential_asset) = checker.identify_asset(trace) else {
+                            continue;
+                        };
+                        let checked =
+                            match checker.check_balances(potential_asset.clone(), &mut executor) {
+                                Ok(checked) => checked,
+                                Err(e) => {
+                                    error!(
+                                        "{:?} error while checking balance: {}",
+                                        checker.asset_type(),
+                                        e
+                                    );
+                                    continue;
+                                }
+                            };
+                        for mut missing in checked {
+                            if missing.missing_amount.is_zero() {
+                                continue;
+                            }
+                            if !seen.insert((
+                                missing.account,
+                                missing.kind,
+                                missing.required.clone(),
+                            )) {
+                                continue;
+                            }
+                            missing.frame = Some(TraceFrame::from_node(node));
+                            Self::attribute_constructor(&mut missing, traces.nodes(), node, &call);
+                            missing.external = !self.is_account_of_interest(
+                                missing.deployer.unwrap_or(missing.account),
+                            );
+                            findings.push(missing.clone());
+                            if missing.external || !missing.kind.is_fundable() {
+                                continue;
+                            }
+                            found_any_missing = true;
+
+                            let ctx =
+                                AssetContext::from_trace(potential_asset.clone(), trace.clone());
+                            info!("Funding {:?} generously", missing.account);
+                            checker.deal(
+                                missing.account,
+                                generous(missing.required.clone()),
+                                &mut executor,
+                                &ctx,
+                            )?;
+                            iteration.deals.push(missing.clone());
+                            if !matches!(
+                                missing.kind,
+                                RequirementKind::Balance | RequirementKind::Ownership
+                            ) {
+                                approvals.push(Dealt {
+                                    checker: index,
+                                    missing,
+                                    context: ctx,
+                                });
+                            }
                         }
                     }
+                }
+            }
+            iterations.push(iteration);
+
+            if found_any_missing {
+                continue;
+            }
+            status = DiscoveryStatus::Reverted;
+            break;
+        }
+
+        let Some(traces) = traces else {
+            // Never got through; what we found on the way is all we know, and
+            // like in revert mode the state keeps what was dealt
+            self.executor = executor;
+            let report = DiscoveryReport {
+                status,
+                iterations,
+                revert_reason,
+                gas_used,
+                requirements: reconcile(findings, None),
+                outflows: Vec::new(),
+                time_requirement: None,
+                blocker: None,
+            };
+            return Ok(Discovery {
+                report,
+                traces: None,
+            });
+        };
+
+        // Requirements are what left each account. Approvals aren't
+        // movements, so those findings are kept as they are.
+        let ledger = Ledger::from_traces(&traces);
+        let outflows = ledger.outflows();
+        let mut requirements: Vec<MissingAssetInfo> = findings
+            .into_iter()
+            .filter(|missing| {
+                !matches!(
+                    missing.kind,
+                    RequirementKind::Balance | RequirementKind::Ownership
+                )
+            })
+            .collect();
+        let fees = HashMap::from([(call.from, gas_fee(&self.executor, &call))]);
+        let deployers = Self::constructor_deployers(traces.nodes(), &outflows, call.from);
+        let measured = self.measured_requirements(&outflows, &fees, &deployers, &self.executor);
+
+        // Carry forward only what was measured, then the tx itself, so kept
+        // state and later bundle steps never see the generous funding. If
+        // that isn't enough to carry the tx, the report says so.
+        self.fund_measured(&measured, &approvals)?;
+        let mut traces = Some(traces);
+        match transact(&mut self.executor, &call) {
+            Ok(result) if !result.reverted => {}
+            confirmation => {
+                error!("Measured funding doesn't carry the tx");
+                status = DiscoveryStatus::Reverted;
+                traces = None;
+                let mut iteration = Iteration {
+                    reverted: true,
+                    deals: measured.clone(),
+                    ..Default::default()
+                };
+                match confirmation {
+                    Ok(result) => {
+                        iteration.gas_used = result.gas_used;
+                        gas_used = result.gas_used;
+                        revert_reason = Some(
+                            RevertDecoder::new().decode(&result.result, Some(result.exit_reason)),
+                        );
+                    }
+                    Err(err) => {
+                        gas_used = 0;
+                        revert_reason = Some(err.to_string());
+                    }
+                }
+                iterations.push(iteration);
+            }
+        }
+        requirements.extend(measured);
+
+        let report = DiscoveryReport {
+            status,
+            iterations,
+            revert_reason,
+            gas_used,
+            requirements: reconcile(requirements, Some(&ledger)),
+            outflows,
+            time_requirement: None,
+            blocker: None,
+        };
+        Ok(Discovery { report, traces })
+    }
+
+    /// Deal exactly what outflow mode measured, and re-deal the approvals it
+    /// found, on the simulator's own state.
+    fn fund_measured(
+        &mut self,
+        measured: &[MissingAssetInfo],
+        approvals: &[Dealt],
+    ) -> Result<(), eyre::Error> {
+        let mut grants = Vec::new();
+        for missing in measured.iter().filter(|missing| !missing.external) {
+            match missing.required {
+                // Fees are measured without a native checker too
+                AssetSpec::Native(amount) => self.executor.set_balance(missing.account, amount)?,
+                _ => grants.push(AssetGrant {
+                    recipient: missing.account,
+                    asset: missing.required.clone(),
+                }),
+            }
+        }
+        self.apply_grants(grants)?;
+
+        for approval in approvals {
+            self.checkers[approval.checker].deal(
+                approval.missing.account,
+                approval.missing.required.clone(),
+                &mut self.executor,
+                &approval.context,
+            )?;
+        }
+        Ok(())
+    }
+
+    // --------------------------------------------------------------------
+    //  Helper: shortfalls of a contract under construction
+    // --------------------------------------------------------------------
+    /// A contract's constructor can't hold anything before it runs, so its
+    /// shortfalls are for whoever sends the deployment. The asset is still
+    /// dealt to the (counterfactual) contract address.
+    fn attribute_constructor(
+        missing: &mut MissingAssetInfo,
+        nodes: &[CallTraceNode],
+        node: &CallTraceNode,
+        call: &Call,
+    ) {
+        if in_constructor_of(nodes, node, missing.account) {
+            missing.deployer = Some(call.from);
+        }
+    }
+
+    /// Outflow-mode counterpart: accounts among `outflows` whose frame runs
+    /// in their own constructor, mapped to `from`, the sender deploying them.
+    fn constructor_deployers(
+        nodes: &[CallTraceNode],
+        outflows: &[Outflow],
+        from: Address,
+    ) -> HashMap<Address, Address> {
+        outflows
+            .iter()
+            .filter(|outflow| {
+                outflow.frame.is_some_and(|frame| {
+                    nodes
+                        .get(frame.index)
+                        .is_some_and(|node| in_constructor_of(nodes, node, outflow.account))
                 })
-                .or_insert(a);
+            })
+            .map(|outflow| (outflow.account, from))
+            .collect()
+    }
+
+    // --------------------------------------------------------------------
+    //  Helper: requirements from measured outflows
+    // --------------------------------------------------------------------
+    /// What each outflow needs on top of its account's balance in `before`.
+    /// Native outflows also cover the gas `fees` their account paid, which
+    /// never show up as transfers. Accounts in `deployers` are charged to
+    /// whoever deploys them.
+    fn measured_requirements(
+        &self,
+        outflows: &[Outflow],
+        fees: &HashMap<Address, U256>,
+        deployers: &HashMap<Address, Address>,
+        before: &Executor,
+    ) -> Vec<MissingAssetInfo> {
+        let mut fees = fees.clone();
+        let mut requirements = Vec::new();
+        for outflow in outflows {
+            let mut needed = outflow.net;
+            if let Holding::Native = outflow.holding {
+                let fee = fees.remove(&outflow.account).unwrap_or_default();
+                needed = needed.saturating_add(fee);
+            }
+            let current_balance = outflow.holding.balance_of(before, outflow.account);
+            let missing_amount = needed.saturating_sub(current_balance);
+            if missing_amount.is_zero() {
+                continue;
+            }
+
+            let mut missing = MissingAssetInfo::new(
+                outflow.account,
+                outflow.holding.spec(needed),
+                current_balance,
+                missing_amount,
+            );
+            if let Holding::ERC721 { .. } = outflow.holding {
+                missing.kind = RequirementKind::Ownership;
+            }
+            missing.deployer = deployers.get(&outflow.account).copied();
+            missing.external =
+                !self.is_account_of_interest(missing.deployer.unwrap_or(outflow.account));
+            missing.frame = outflow.frame;
+            requirements.push(missing);
+        }
+
+        // Senders that paid gas without sending any native value
+        for (account, fee) in fees {
+            let current_balance = Holding::Native.balance_of(before, account);
+            let missing_amount = fee.saturating_sub(current_balance);
+            if missing_amount.is_zero() {
+                continue;
+            }
+            let mut missing = MissingAssetInfo::new(
+                account,
+                AssetSpec::Native(fee),
+                current_balance,
+                missing_amount,
+            );
+            missing.external = !self.is_account_of_interest(account);
+            requirements.push(missing);
+        }
+        requirements
+    }
+
+    // --------------------------------------------------------------------
+    //  Helper: smallest balances that let the tx pass (exact strategy)
+    // --------------------------------------------------------------------
+    /// With every later requirement generous, grow each dealt requirement in
+    /// turn from its exact amount until the tx passes, then bisect. Returns
+    /// the state holding all minimums, or `None` if not even generous
+    /// balances make the tx pass.
+    fn search_minimums(
+        &self,
+        call: &Call,
+        dealt: &mut [Dealt],
+    ) -> Result<Option<Executor>, eyre::Error> {
+        let plenty = U256::MAX >> 1;
+        let deal = |executor: &mut Executor, dealt: &Dealt, amount: U256| {
+            match with_amount(&dealt.missing.required, amount) {
+                Some(spec) => self.checkers[dealt.checker].deal(
+                    dealt.missing.account,
+                    spec,
+                    executor,
+                    &dealt.context,
+                ),
+                None => Ok(()), // Non-fungible: already dealt in full
+            }
+        };
+
+        let mut generous_stage = self.executor.clone();
+        for requirement in dealt.iter() {
+            deal(&mut generous_stage, requirement, plenty)?;
+        }
+        if !passes(&generous_stage, call) {
+            return Ok(None);
+        }
+
+        let mut stage = self.executor.clone();
+        for i in 0..dealt.len() {
+            let Some(exact) = fungible_amount(&dealt[i].missing.required) else {
+                continue;
+            };
+            // Deals never lower a holding, so one dealt several times (e.g.
+            // more in a later round) is searched once, at its last deal and
+            // from its largest amount
+            let holding = holding_of(&dealt[i].missing);
+            if dealt[i + 1..]
+                .iter()
+                .any(|later| holding_of(&later.missing) == holding)
+            {
+                continue;
+            }
+            let exact = dealt[..i]
+                .iter()
+                .filter(|earlier| holding_of(&earlier.missing) == holding)
+                .filter_map(|earlier| fungible_amount(&earlier.missing.required))
+                .fold(exact, U256::max);
+            let mut base = stage.clone();
+            for later in &dealt[i + 1..] {
+                deal(&mut base, later, plenty)?;
+            }
+            let passes_with = |amount: U256| -> Result<bool, eyre::Error> {
+                let mut probe = base.clone();
+                deal(&mut probe, &dealt[i], amount)?;
+                Ok(passes(&probe, call))
+            };
+
+            let minimum = if passes_with(exact)? {
+                exact
+            } else {
+                // `plenty` passes: everything before is at its minimum and
+                // everything after is generous, as verified last round
+                let (mut lo, mut hi) = (exact, exact);
+                loop {
+                    hi = hi
+                        .saturating_mul(U256::from(2))
+                        .max(U256::from(1))
+                        .min(plenty);
+                    if hi == plenty || passes_with(hi)? {
+                        break;
+                    }
+                    lo = hi;
+                }
+                while hi - lo > U256::from(1) {
+                    let mid = lo + (hi - lo) / U256::from(2);
+                    if passes_with(mid)? {
+                        hi = mid;
+                    } else {
+                        lo = mid;
+                    }
+                }
+                hi
+            };
+
+            info!(
+                "Verified minimum for {:?}: {}",
+                dealt[i].missing.account, minimum
+            );
+            deal(&mut stage, &dealt[i], minimum)?;
+            for same in dealt[..=i]
+                .iter_mut()
+                .filter(|same| holding_of(&same.missing) == holding)
+            {
+                same.missing.verified_minimum = Some(minimum);
+            }
+        }
+        Ok(Some(stage))
+    }
+
+    // --------------------------------------------------------------------
+    //  Helper: backend snapshots (around a check, or named)
+    // --------------------------------------------------------------------
+    fn snapshot_state(&mut self) -> U256 {
+        let journaled_state = JournaledState::new(self.executor.spec_id(), HashSet::new());
+        let env = Env::clone(self.executor.env());
+        self.executor
+            .backend_mut()
+            .snapshot_state(&journaled_state, &env)
+    }
+
+    fn revert_state(&mut self, snapshot: U256) {
+        if !self.restore_state(snapshot, RevertStateSnapshotAction::RevertRemove) {
+            error!(
+                "Backend snapshot {} is gone; state was not reverted",
+                snapshot
+            );
+        }
+    }
+
+    fn restore_state(&mut self, snapshot: U256, action: RevertStateSnapshotAction) -> bool {
+        let journaled_state = JournaledState::new(self.executor.spec_id(), HashSet::new());
+        let mut env = Env::clone(self.executor.env());
+        self.executor
+            .backend_mut()
+            .revert_state(snapshot, &journaled_state, &mut env, action)
+            .is_some()
+    }
+
+    // --------------------------------------------------------------------
+    //  Helper: native shortfall for the tx's upfront cost
+    // --------------------------------------------------------------------
+    fn upfront_shortfall(
+        executor: &Executor,
+        call: &Call,
+    ) -> Result<Option<MissingAssetInfo>, eyre::Error> {
+        let env = tx_env(executor, call);
+        let cost = upfront_cost(env.tx.gas_limit, env.tx.gas_price, call.value);
+        let current_balance = executor.get_balance(call.from)?;
+        if current_balance >= cost {
+            return Ok(None);
+        }
+
+        Ok(Some(MissingAssetInfo::new(
+            call.from,
+            AssetSpec::Native(cost),
+            current_balance,
+            cost - current_balance,
+        )))
+    }
+}
+
+/// A discovery's report, plus the traces of its last run if it succeeded.
+struct Discovery {
+    report: DiscoveryReport,
+    traces: Option<SparsedTraceArena>,
+}
+
+/// A requirement dealt in revert mode, kept to re-deal other amounts.
+struct Dealt {
+    checker: usize, // Index into `AssetSimulator::checkers`
+    missing: MissingAssetInfo,
+    context: AssetContext,
+}
+
+/// Whether `report` asks for something a deal could fix that none of its
+/// runs dealt, e.g. with `auto_fix` off.
+fn has_unfunded_requirements(report: &DiscoveryReport) -> bool {
+    let dealt: Vec<MissingAssetInfo> = report
+        .iterations
+        .iter()
+        .flat_map(|iteration| iteration.deals.iter().cloned())
+        .collect();
+    let fundable: Vec<MissingAssetInfo> = report
+        .requirements
+        .iter()
+        .filter(|missing| !missing.external && missing.kind.is_fundable())
+        .cloned()
+        .collect();
+    has_new_requirements(&dealt, &fundable)
+}
+
+/// Gas fee the sender of `call` must be able to pay, on top of its value.
+fn gas_fee(executor: &Executor, call: &Call) -> U256 {
+    let env = tx_env(executor, call);
+    upfront_cost(env.tx.gas_limit, env.tx.gas_price, U256::ZERO)
+}
+
+/// The env `call` runs in: the executor's, with the call's envelope on top.
+pub(crate) fn tx_env(executor: &Executor, call: &Call) -> EnvWithHandlerCfg {
+    let mut env = executor.build_test_env(
+        call.from,
+        TxKind::from(call.to),
+        call.data.clone(),
+        call.value,
+    );
+    // `build_test_env` zeroes the basefee so calls run at a zero gas price;
+    // only a call that sets its own price is held to the configured one
+    if call.gas_price.is_some() {
+        env.block.basefee = executor.env().block.basefee;
+    }
+    if let Some(gas_limit) = call.gas_limit {
+        env.tx.gas_limit = gas_limit;
+    }
+    if let Some(gas_price) = call.gas_price {
+        env.tx.gas_price = gas_price;
+    }
+    env.tx.gas_priority_fee = call.max_priority_fee_per_gas;
+    env.tx.nonce = call.nonce;
+    env.tx.access_list = call.access_list.clone();
+    if !call.authorization_list.is_empty() {
+        env.tx.authorization_list =
+            Some(AuthorizationList::Signed(call.authorization_list.clone()));
+    }
+    env
+}
+
+/// Run `call` and commit its state changes. A reverted run doesn't use up
+/// the sender's nonce, so the re-run can still use a pinned nonce and a
+/// deployment goes to the same address.
+fn transact(executor: &mut Executor, call: &Call) -> Result<RawCallResult, eyre::Error> {
+    let nonce = executor.get_nonce(call.from)?;
+    let env = tx_env(executor, call);
+    let result = executor.transact_with_env(env)?;
+    if result.reverted {
+        executor.set_nonce(call.from, nonce)?;
+    }
+    Ok(result)
+}
+
+/// Whether `call` goes through on `executor`'s state (nothing is committed).
+fn passes(executor: &Executor, call: &Call) -> bool {
+    executor
+        .call_with_env(tx_env(executor, call))
+        .is_ok_and(|result| !result.reverted)
+}
+
+/// What revert mode deals for `spec`. Generous only inflates ERC-20
+/// balances; native and NFT deals are exact either way.
+fn deal_target(spec: AssetSpec, strategy: DealStrategy) -> AssetSpec {
+    match (strategy, spec) {
+        (DealStrategy::Generous, AssetSpec::ERC20 { token, .. }) => AssetSpec::ERC20 {
+            token,
+            amount: U256::MAX >> 1,
+        },
+        (_, spec) => spec,
+    }
+}
+
+/// Amount of a single-balance spec; `None` for NFTs and multi-id batches.
+fn fungible_amount(spec: &AssetSpec) -> Option<U256> {
+    match spec {
+        AssetSpec::Native(amount) | AssetSpec::ERC20 { amount, .. } => Some(*amount),
+        AssetSpec::ERC1155 { token_amounts, .. } if token_amounts.len() == 1 => {
+            token_amounts.values().next().copied()
         }
+        _ => None,
+    }
+}
+
+/// The single balance `missing` is about, regardless of the amount.
+fn holding_of(missing: &MissingAssetInfo) -> (Address, RequirementKind, Option<AssetSpec>) {
+    (
+        missing.account,
+        missing.kind,
+        with_amount(&missing.required, U256::ZERO),
+    )
+}
+
+/// `spec` with its single balance replaced by `amount`.
+fn with_amount(spec: &AssetSpec, amount: U256) -> Option<AssetSpec> {
+    match spec {
+        AssetSpec::Native(_) => Some(AssetSpec::Native(amount)),
+        AssetSpec::ERC20 { token, .. } => Some(AssetSpec::ERC20 {
+            token: *token,
+            amount,
+        }),
+        AssetSpec::ERC1155 {
+            token,
+            token_amounts,
+        } if token_amounts.len() == 1 => Some(AssetSpec::ERC1155 {
+            token: *token,
+            token_amounts: token_amounts.keys().map(|&id| (id, amount)).collect(),
+        }),
+        _ => None,
+    }
+}
 
-        map.into_values().collect()
+/// The same asset in an amount no realistic transaction runs out of.
+fn generous(spec: AssetSpec) -> AssetSpec {
+    let plenty = U256::MAX >> 1;
+    match spec {
+        AssetSpec::Native(_) => AssetSpec::Native(plenty),
+        AssetSpec::ERC20 { token, .. } => AssetSpec::ERC20 {
+            token,
+            amount: plenty,
+        },
+        AssetSpec::ERC1155 {
+            token,
+            token_amounts,
+        } => AssetSpec::ERC1155 {
+            token,
+            token_amounts: token_amounts.into_keys().map(|id| (id, plenty)).collect(),
+        },
+        spec @ AssetSpec::ERC721 { .. } => spec,
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::simulate::blocker::BlockerCategory;
+    use crate::simulate::checkers::erc20::{
+        permitCall, receiveWithAuthorizationCall, transferWithAuthorizationCall,
+    };
+    use crate::simulate::checkers::erc1155::IERC1155;
+    use crate::simulate::overrides::{AccountOverride, StateOverride};
+    use crate::simulate::types::{AuthorizationFailure, PermitFailure};
     use crate::simulate::{checkers::erc20::transferFromCall, types::AssetSpec};
-    use alloy_primitives::Address as AAddress;
+    use alloy_primitives::{Address as AAddress, B256};
     use alloy_sol_types::{SolCall, sol};
-    use forge::revm::primitives::{Address, Bytes, U256};
+    use forge::revm::primitives::{Address, Bytecode, Bytes, SpecId, U256};
     use std::str::FromStr;
 
     sol!(
@@ -177,6 +1290,11 @@ mod tests {
         contract IERC20 {
             function transfer(address to, uint256 amount) public returns (bool);
         }
+
+        contract MockERC721 {
+            function ownerOf(uint256 tokenId) public view returns (address);
+            function balanceOf(address owner) public view returns (uint256);
+        }
     );
 
     // Helper function for minting tokens in tests
@@ -205,12 +1323,15 @@ mod tests {
 
     // Simplified setup that just deploys the contract and returns basic info
     async fn setup_local_erc20_test() -> Result<(AssetSimulator, Address), eyre::Error> {
+        setup_local_erc20_test_with(AssetSimulator::builder().with_erc20_checker()).await
+    }
+
+    async fn setup_local_erc20_test_with(
+        builder: AssetSimulatorBuilder,
+    ) -> Result<(AssetSimulator, Address), eyre::Error> {
         let minter = Address::repeat_byte(4);
 
-        let mut simulator = AssetSimulator::builder()
-            .with_erc20_checker()
-            .build()
-            .await?;
+        let mut simulator = builder.build().await?;
 
         let exec = simulator.executor_mut();
 
@@ -226,31 +1347,1321 @@ mod tests {
             None,       // No revert decoder
         )?;
 
-        let contract_address = deploy_result.address;
+        let contract_address = deploy_result.address;
+
+        Ok((simulator, contract_address))
+    }
+
+    // Minimal ERC-2612 / EIP-3009 token keeping balances, allowances, permit
+    // nonces and authorization states in mappings at slots 0 to 3, with a
+    // fixed `DOMAIN_SEPARATOR` of keccak256("PermitToken")
+    const PERMIT_TOKEN: &str = "\
+        6103f48061000d6000396000f360003560e01c806370a082311461007a578063dd62ed3e\
+        146100945780637ecebe00146100bc5780633644e515146100d6578063e94a0102146101\
+        00578063a9059cbb1461012857806323b872dd14610137578063d505accf146101815780\
+        63e3ee160e146102a0578063ef55bec614610270575b60006000fd5b6004356000526000\
+        60205260406000205460005260206000f35b600435600052600160205260406000206024\
+        3560005260205260406000205460005260206000f35b6004356000526002602052604060\
+        00205460005260206000f35b7f25a4d738922a372a8c250edc3e5732cdc7fbb2b353145c\
+        5efcd2aa1038f753fe60005260206000f35b600435600052600360205260406000206024\
+        3560005260205260406000205460005260206000f35b6101763360043560243561039356\
+        5b6004356000526001602052604060002033600052602052604060002080546044358110\
+        6100745760443590039055610176600435602435604435610393565b6001600052602060\
+        00f35b6064354211610074576004356000526002602052604060002080547f6e71edae12\
+        b1b97f4d1f60370fef10105fa2faae0126114a169c64845d6126c9600052606060046020\
+        378060805260643560a05260c060002061190160f01b6000527f25a4d738922a372a8c25\
+        0edc3e5732cdc7fbb2b353145c5efcd2aa1038f753fe6002526022526042600020600052\
+        60843560205260a43560405260c43560605260006102005260206102006080600060015a\
+        fa5061020051801561007457600435141561007457600101905560443560043560005260\
+        016020526040600020602435600052602052604060002055005b60243533141561007457\
+        7fd099cc98ef71107a616c4f0f941f04c322d8e254fe26b3c6668db87aae413de86102c2\
+        565b7f7c7c6cdb67a18743f49ec6fa9b35f50d52ed05cbed4cc592e13b44501c1a22675b\
+        606435421115610074576084354210156100745760043560005260036020526040600020\
+        60a43560005260205260406000208054610074578160005260c0600460203760e0600020\
+        61190160f01b6000527f25a4d738922a372a8c250edc3e5732cdc7fbb2b353145c5efcd2\
+        aa1038f753fe600252602252604260002060005260c43560205260e43560405261010435\
+        60605260006102005260206102006080600060015afa5061020051801561007457600435\
+        14156100745760019055610391600435602435604435610393565b005b82600052600060\
+        205260406000208054828110610074578290039055816000526000602052604060002080\
+        54820190558060005281837fddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a116\
+        28f55a4df523b3ef60206000a350505056";
+
+    // Address of private key 0xa11ce, which made the signatures below
+    const PERMIT_OWNER: &str = "0xe05fcc23807536bee418f142d19fa0d21bb0cff7";
+
+    type Signature = (u8, &'static str, &'static str);
+
+    // Permit of 100 tokens from the owner to `0x5e..5e` over nonce 0, deadline 2000
+    const PERMIT_SIGNATURE: Signature = (
+        28,
+        "fb4fa244ea40f93c439c6ec729eceec4791a8403de6095a74258d748ee62f6b5",
+        "0e9ccc8090a1df29bfba6780ff6af0dad805cf37cc2b8be8a1db96c7907be20e",
+    );
+    // The same over nonce 1
+    const PERMIT_SIGNATURE_NONCE_1: Signature = (
+        27,
+        "cced63b0079ce746bd901652c6330fdceefae4ab83c941932483d8f8a6a2e66d",
+        "432b66f35016273574144e63ccec4be148793125d9b077b698dc79a8f19c25ab",
+    );
+    // The same over nonce 0, signed by private key 0xb0b
+    const PERMIT_SIGNATURE_OTHER_KEY: Signature = (
+        28,
+        "683835a5d06de0a27f5278c85181364934f99dbc081d49a1ed655d67a43883b5",
+        "3987e68ac16783ef4d0087996dc03f0ffc493df3ed24b20623f50530919c9480",
+    );
+
+    async fn setup_permit_token_test() -> Result<(AssetSimulator, Address), eyre::Error> {
+        let mut simulator = AssetSimulator::builder()
+            .with_erc20_checker()
+            .with_block_timestamp(1_000)
+            .build()
+            .await?;
+        let token = simulator
+            .executor_mut()
+            .deploy(
+                Address::repeat_byte(4),
+                Bytes::from_str(PERMIT_TOKEN)?,
+                U256::ZERO,
+                None,
+            )?
+            .address;
+        Ok((simulator, token))
+    }
+
+    fn permit(spender: Address, deadline: u64, (v, r, s): Signature) -> Vec<u8> {
+        permitCall {
+            owner: PERMIT_OWNER.parse().unwrap(),
+            spender: AAddress::from_slice(spender.as_slice()),
+            value: U256::from(100),
+            deadline: U256::from(deadline),
+            v,
+            r: r.parse().unwrap(),
+            s: s.parse().unwrap(),
+        }
+        .abi_encode()
+    }
+
+    // Transfer of 50 tokens from the owner to `0xb0..b0` with nonce `0x01..01`,
+    // valid from 0 to 2000
+    const AUTHORIZATION_SIGNATURE: Signature = (
+        28,
+        "2b9f59614fa6bd9a729a7757e89a26c6582feeb7abf7209edde121d8206c4d38",
+        "4ff49e2a10b139bda8fb220023439d7cb0ce12d3237a73b62fa5a7e123ec1356",
+    );
+    // The same signed by private key 0xb0b
+    const AUTHORIZATION_SIGNATURE_OTHER_KEY: Signature = (
+        27,
+        "3a363afae67151b25ba7137a260543fa51f3337f6170353dc6c7211ebe653ab0",
+        "55394a8e7edecd6eeabe901d03641bfbc52b76bd5f7f8bf240b67686258b4db7",
+    );
+
+    fn authorization(
+        receive: bool,
+        valid_after: u64,
+        valid_before: u64,
+        (v, r, s): Signature,
+    ) -> Vec<u8> {
+        let (from, to, value) = (
+            PERMIT_OWNER.parse().unwrap(),
+            AAddress::repeat_byte(0xb0),
+            U256::from(50),
+        );
+        let (valid_after, valid_before) = (U256::from(valid_after), U256::from(valid_before));
+        let (nonce, r, s) = (B256::repeat_byte(1), r.parse().unwrap(), s.parse().unwrap());
+        if receive {
+            receiveWithAuthorizationCall {
+                from,
+                to,
+                value,
+                validAfter: valid_after,
+                validBefore: valid_before,
+                nonce,
+                v,
+                r,
+                s,
+            }
+            .abi_encode()
+        } else {
+            transferWithAuthorizationCall {
+                from,
+                to,
+                value,
+                validAfter: valid_after,
+                validBefore: valid_before,
+                nonce,
+                v,
+                r,
+                s,
+            }
+            .abi_encode()
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_successful_transfer_returns_empty() -> Result<(), eyre::Error> {
+        let (mut simulator, contract_address) = setup_local_erc20_test().await?;
+
+        let sender = Address::from_str("0x1000000000000000000000000000000000000001").unwrap();
+        let recipient = Address::from_str("0x2000000000000000000000000000000000000002").unwrap();
+        let minter = Address::repeat_byte(4);
+        let amount = U256::from(100);
+
+        // Mint tokens to sender
+        mint_tokens(
+            &mut simulator,
+            contract_address,
+            minter,
+            sender,
+            amount * U256::from(2),
+        )
+        .await?;
+
+        // Test a successful transfer
+        let transfer_call = Call::new(
+            sender,
+            contract_address,
+            MockERC20::transferCall {
+                to: AAddress::from_slice(recipient.as_slice()),
+                amount,
+            }
+            .abi_encode(),
+            U256::ZERO,
+        );
+
+        let result = simulator
+            .check_transaction(transfer_call)
+            .await?
+            .requirements;
+        assert!(
+            result.is_empty(),
+            "Successful transfer should return no missing assets"
+        );
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_insufficient_balance_detected() -> Result<(), eyre::Error> {
+        let (mut simulator, contract_address) = setup_local_erc20_test().await?;
+
+        let sender = Address::from_str("0x1000000000000000000000000000000000000001").unwrap();
+        let recipient = Address::from_str("0x2000000000000000000000000000000000000002").unwrap();
+        let amount = U256::from(100);
+
+        // Test transfer with insufficient balance (sender has 0 tokens in this fresh simulator)
+        let transfer_call = Call::new(
+            sender,
+            contract_address,
+            MockERC20::transferCall {
+                to: AAddress::from_slice(recipient.as_slice()),
+                amount,
+            }
+            .abi_encode(),
+            U256::ZERO,
+        );
+
+        // Use no_fix version to just detect without fixing
+        let result = simulator
+            .check_transaction(transfer_call)
+            .await?
+            .requirements;
+
+        assert!(!result.is_empty(), "Should detect missing balance");
+
+        if let Some(asset) = result.first() {
+            assert_eq!(asset.account, sender);
+            assert_eq!(asset.missing_amount, amount);
+            assert_eq!(asset.current_balance, U256::ZERO);
+
+            assert_eq!(
+                asset.required,
+                AssetSpec::ERC20 {
+                    token: contract_address,
+                    amount
+                }
+            );
+
+            // The transfer is the top-level frame
+            let frame = asset.frame.expect("finding should reference its frame");
+            assert_eq!(frame.depth, 0);
+            assert_eq!(frame.address, contract_address);
+        }
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_report_tracks_deals_and_status() -> Result<(), eyre::Error> {
+        let (mut simulator, contract_address) = setup_local_erc20_test().await?;
+
+        let sender = Address::from_str("0x1000000000000000000000000000000000000001").unwrap();
+        let recipient = Address::from_str("0x2000000000000000000000000000000000000002").unwrap();
+        let amount = U256::from(100);
+        let transfer = || {
+            Call::new(
+                sender,
+                contract_address,
+                MockERC20::transferCall {
+                    to: AAddress::from_slice(recipient.as_slice()),
+                    amount,
+                }
+                .abi_encode(),
+                U256::ZERO,
+            )
+        };
+
+        // Detection only: still failing, with the token's reason
+        let report = simulator
+            .check_transaction_with_options(
+                transfer(),
+                CheckOptions {
+                    auto_fix: false,
+                    ..Default::default()
+                },
+            )
+            .await?;
+        assert_eq!(report.status, DiscoveryStatus::Reverted);
+        assert_eq!(report.iterations.len(), 1);
+        assert!(report.iterations[0].deals.is_empty());
+        assert!(
+            report
+                .revert_reason
+                .as_deref()
+                .is_some_and(|reason| reason.contains("exceeds balance")),
+            "unexpected revert reason: {:?}",
+            report.revert_reason
+        );
+
+        // Auto-fix: one round to deal, one to confirm
+        let report = simulator.check_transaction(transfer()).await?;
+        assert!(report.succeeded());
+        assert_eq!(report.iterations.len(), 2);
+        assert_eq!(report.iterations[0].deals.len(), 1);
+        assert!(!report.iterations[1].reverted);
+        assert!(report.revert_reason.is_none());
+        assert!(report.gas_used > 0);
+        assert_eq!(report.requirements.len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_outflow_mode_measures_transfers() -> Result<(), eyre::Error> {
+        let (mut simulator, contract_address) = setup_local_erc20_test().await?;
+
+        let sender = Address::from_str("0x1000000000000000000000000000000000000001").unwrap();
+        let recipient = Address::from_str("0x2000000000000000000000000000000000000002").unwrap();
+        let minter = Address::repeat_byte(4);
+        let amount = U256::from(100);
+
+        // Holds 40 of the 100 it sends
+        mint_tokens(
+            &mut simulator,
+            contract_address,
+            minter,
+            sender,
+            U256::from(40),
+        )
+        .await?;
+
+        let transfer_call = Call::new(
+            sender,
+            contract_address,
+            MockERC20::transferCall {
+                to: AAddress::from_slice(recipient.as_slice()),
+                amount,
+            }
+            .abi_encode(),
+            U256::ZERO,
+        );
+        let report = simulator
+            .check_transaction_with_options(
+                transfer_call,
+                CheckOptions {
+                    mode: DiscoveryMode::Outflow,
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        assert!(report.succeeded());
+        assert_eq!(report.outflows.len(), 1);
+        let outflow = &report.outflows[0];
+        assert_eq!(outflow.account, sender);
+        assert_eq!(
+            outflow.holding,
+            Holding::ERC20 {
+                token: contract_address
+            }
+        );
+        assert_eq!(outflow.gross, amount);
+        assert_eq!(outflow.net, amount);
+
+        assert_eq!(report.requirements.len(), 1);
+        let missing = &report.requirements[0];
+        assert_eq!(missing.account, sender);
+        assert_eq!(missing.current_balance, U256::from(40));
+        assert_eq!(missing.missing_amount, U256::from(60));
+
+        // The generous funding happened on a copy
+        let balance = simulator.executor_mut().call_raw(
+            Address::ZERO,
+            contract_address,
+            MockERC20::balanceOfCall {
+                account: AAddress::from_slice(sender.as_slice()),
+            }
+            .abi_encode()
+            .into(),
+            U256::ZERO,
+        )?;
+        assert_eq!(
+            MockERC20::balanceOfCall::abi_decode_returns(&balance.result)?,
+            U256::from(40)
+        );
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_outflow_mode_keeps_only_measured_funding() -> Result<(), eyre::Error> {
+        let (mut simulator, contract_address) = setup_local_erc20_test().await?;
+
+        let sender = Address::from_str("0x1000000000000000000000000000000000000001").unwrap();
+        let recipient = Address::from_str("0x2000000000000000000000000000000000000002").unwrap();
+        let minter = Address::repeat_byte(4);
+        mint_tokens(
+            &mut simulator,
+            contract_address,
+            minter,
+            sender,
+            U256::from(40),
+        )
+        .await?;
+
+        let transfer_call = Call::new(
+            sender,
+            contract_address,
+            MockERC20::transferCall {
+                to: AAddress::from_slice(recipient.as_slice()),
+                amount: U256::from(100),
+            }
+            .abi_encode(),
+            U256::ZERO,
+        );
+        let report = simulator
+            .check_transaction_with_options(
+                transfer_call,
+                CheckOptions {
+                    mode: DiscoveryMode::Outflow,
+                    keep_state: true,
+                    ..Default::default()
+                },
+            )
+            .await?;
+        assert!(report.succeeded());
+
+        // The sender got the 60 it was short of, not the generous funding,
+        // and then sent all of it
+        let balance_of =
+            |simulator: &mut AssetSimulator, account: Address| -> Result<U256, eyre::Error> {
+                let balance = simulator.executor_mut().call_raw(
+                    Address::ZERO,
+                    contract_address,
+                    MockERC20::balanceOfCall {
+                        account: AAddress::from_slice(account.as_slice()),
+                    }
+                    .abi_encode()
+                    .into(),
+                    U256::ZERO,
+                )?;
+                Ok(MockERC20::balanceOfCall::abi_decode_returns(
+                    &balance.result,
+                )?)
+            };
+        assert_eq!(balance_of(&mut simulator, sender)?, U256::ZERO);
+        assert_eq!(balance_of(&mut simulator, recipient)?, U256::from(100));
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_exact_deal_reports_verified_minimum() -> Result<(), eyre::Error> {
+        let (mut simulator, contract_address) = setup_local_erc20_test().await?;
+
+        let sender = Address::from_str("0x1000000000000000000000000000000000000001").unwrap();
+        let recipient = Address::from_str("0x2000000000000000000000000000000000000002").unwrap();
+        let amount = U256::from(100);
+
+        let transfer_call = Call::new(
+            sender,
+            contract_address,
+            MockERC20::transferCall {
+                to: AAddress::from_slice(recipient.as_slice()),
+                amount,
+            }
+            .abi_encode(),
+            U256::ZERO,
+        );
+        let report = simulator
+            .check_transaction_with_options(
+                transfer_call,
+                CheckOptions {
+                    deal_strategy: DealStrategy::Exact,
+                    keep_state: true,
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        assert!(report.succeeded());
+        assert_eq!(report.requirements.len(), 1);
+        assert_eq!(report.requirements[0].verified_minimum, Some(amount));
+
+        // Exactly the shortfall was dealt, and all of it was spent
+        let balance = simulator.executor_mut().call_raw(
+            Address::ZERO,
+            contract_address,
+            MockERC20::balanceOfCall {
+                account: AAddress::from_slice(sender.as_slice()),
+            }
+            .abi_encode()
+            .into(),
+            U256::ZERO,
+        )?;
+        assert_eq!(
+            MockERC20::balanceOfCall::abi_decode_returns(&balance.result)?,
+            U256::ZERO
+        );
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_exact_deal_grows_and_bisects_minimums() -> Result<(), eyre::Error> {
+        let (mut simulator, contract_address) = setup_local_erc20_test_with(
+            AssetSimulator::builder()
+                .with_erc20_checker()
+                .with_allowance_checker(),
+        )
+        .await?;
+        let alice = Address::repeat_byte(0xa1);
+        let bob = Address::repeat_byte(0xb0);
+        let relay = Address::repeat_byte(0x5e);
+
+        // Runtime: `token.transferFrom(msg.sender, to, 100)`, then the same
+        // for 50, with `(token, to)` as calldata, bubbling up reverts
+        let relay_code = Bytes::from_str(
+            "6323b872dd60e01b600052336004526020356024526064604452600060006064600060\
+             006000355af115610048576032604452600060006064600060006000355af115610048\
+             57005b3d600060003e3d6000fd",
+        )?;
+        simulator
+            .executor_mut()
+            .set_code(relay, Bytecode::new_raw(relay_code))?;
+
+        // Only the first pull shows up as a shortfall; dealing exactly that
+        // leaves the second one short, so both minimums have to be searched
+        let report = simulator
+            .check_transaction_with_options(
+                Call::new(
+                    alice,
+                    relay,
+                    [
+                        contract_address.into_word().to_vec(),
+                        bob.into_word().to_vec(),
+                    ]
+                    .concat(),
+                    U256::ZERO,
+                ),
+                CheckOptions {
+                    deal_strategy: DealStrategy::Exact,
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        assert!(report.succeeded());
+        assert_eq!(report.requirements.len(), 2);
+        for missing in &report.requirements {
+            assert_eq!(missing.account, alice);
+            assert_eq!(
+                missing.required,
+                AssetSpec::ERC20 {
+                    token: contract_address,
+                    amount: U256::from(100)
+                }
+            );
+            assert_eq!(missing.verified_minimum, Some(U256::from(150)));
+        }
+        let mut kinds: Vec<_> = report.requirements.iter().map(|m| m.kind).collect();
+        kinds.sort();
+        assert_eq!(
+            kinds,
+            vec![
+                RequirementKind::Balance,
+                RequirementKind::Allowance { spender: relay }
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_grants_cover_shortfall() -> Result<(), eyre::Error> {
+        let (mut simulator, contract_address) = setup_local_erc20_test().await?;
+
+        let sender = Address::from_str("0x1000000000000000000000000000000000000001").unwrap();
+        let recipient = Address::from_str("0x2000000000000000000000000000000000000002").unwrap();
+        let amount = U256::from(100);
+        let transfer = || {
+            Call::new(
+                sender,
+                contract_address,
+                MockERC20::transferCall {
+                    to: AAddress::from_slice(recipient.as_slice()),
+                    amount,
+                }
+                .abi_encode(),
+                U256::ZERO,
+            )
+        };
+        let grant = |amount: u64| AssetGrant::erc20(sender, contract_address, U256::from(amount));
+
+        assert!(!simulator.verify_with_grants(transfer(), vec![]).await?);
+        assert!(
+            !simulator
+                .verify_with_grants(transfer(), vec![grant(99)])
+                .await?
+        );
+        assert!(
+            simulator
+                .verify_with_grants(transfer(), vec![grant(100)])
+                .await?
+        );
+
+        // Verifying leaves nothing behind
+        let report = simulator.check_transaction(transfer()).await?;
+        assert_eq!(report.requirements.len(), 1);
+
+        // Applied grants do stay
+        simulator.apply_grants(vec![grant(100)])?;
+        let report = simulator.check_transaction(transfer()).await?;
+        assert!(report.succeeded());
+        assert!(report.requirements.is_empty());
+
+        // A smaller grant doesn't take any of it away
+        simulator.apply_grants(vec![grant(50)])?;
+        let report = simulator.check_transaction(transfer()).await?;
+        assert!(report.succeeded());
+        assert!(report.requirements.is_empty());
+
+        // No checker for native assets was added
+        assert!(
+            simulator
+                .apply_grants(vec![AssetGrant::native(sender, U256::from(1))])
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_builder_applies_grants() -> Result<(), eyre::Error> {
+        let alice = Address::repeat_byte(0xa1);
+        let mut simulator = AssetSimulator::builder()
+            .with_native_checker()
+            .with_grants([AssetGrant::native(alice, U256::from(5))])
+            .build()
+            .await?;
+
+        assert_eq!(simulator.executor_mut().get_balance(alice)?, U256::from(5));
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_bundle_attributes_steps_and_sums_funding() -> Result<(), eyre::Error> {
+        let (mut simulator, contract_address) = setup_local_erc20_test().await?;
+
+        let alice = Address::repeat_byte(0xa1);
+        let bob = Address::repeat_byte(0xb0);
+        let carol = Address::repeat_byte(0xc0);
+        let transfer = |from: Address, to: Address, amount: u64| {
+            Call::new(
+                from,
+                contract_address,
+                MockERC20::transferCall {
+                    to: AAddress::from_slice(to.as_slice()),
+                    amount: U256::from(amount),
+                }
+                .abi_encode(),
+                U256::ZERO,
+            )
+        };
+
+        // Bob passes on what he gets; alice pays twice
+        let report = simulator
+            .check_bundle(vec![
+                transfer(alice, bob, 100),
+                transfer(bob, carol, 100),
+                transfer(alice, carol, 50),
+            ])
+            .await?;
+        assert!(report.succeeded());
+        assert_eq!(report.steps.len(), 3);
+
+        // Alice's first deal covers her second transfer
+        assert_eq!(report.requirements.len(), 1);
+        assert_eq!(report.requirements[0].step, 0);
+        assert_eq!(report.requirements[0].requirement.account, alice);
+
+        // But she needs both upfront, and bob needs nothing
+        assert_eq!(report.funding.len(), 1);
+        assert_eq!(report.funding[0].account, alice);
+        assert_eq!(
+            report.funding[0].required,
+            AssetSpec::ERC20 {
+                token: contract_address,
+                amount: U256::from(150)
+            }
+        );
+        assert_eq!(report.funding[0].missing_amount, U256::from(150));
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_named_snapshots_and_branches() -> Result<(), eyre::Error> {
+        let (mut simulator, contract_address) = setup_local_erc20_test().await?;
+
+        let sender = Address::from_str("0x1000000000000000000000000000000000000001").unwrap();
+        let recipient = Address::from_str("0x2000000000000000000000000000000000000002").unwrap();
+        let amount = U256::from(100);
+        let transfer = || {
+            Call::new(
+                sender,
+                contract_address,
+                MockERC20::transferCall {
+                    to: AAddress::from_slice(recipient.as_slice()),
+                    amount,
+                }
+                .abi_encode(),
+                U256::ZERO,
+            )
+        };
+        let grant = AssetGrant::erc20(sender, contract_address, amount);
+
+        simulator.snapshot("clean");
+        let mut funded = simulator.branch("clean")?;
+        funded.apply_grants(vec![grant.clone()])?;
+
+        // The branch has its own state
+        assert!(
+            funded
+                .check_transaction(transfer())
+                .await?
+                .requirements
+                .is_empty()
+        );
+        assert_eq!(
+            simulator
+                .check_transaction(transfer())
+                .await?
+                .requirements
+                .len(),
+            1
+        );
+
+        // Reverting works more than once
+        for _ in 0..2 {
+            simulator.apply_grants(vec![grant.clone()])?;
+            simulator.revert_to("clean")?;
+            assert_eq!(
+                simulator
+                    .check_transaction(transfer())
+                    .await?
+                    .requirements
+                    .len(),
+                1
+            );
+        }
+
+        assert!(simulator.delete_snapshot("clean"));
+        assert!(simulator.revert_to("clean").is_err());
+        assert!(simulator.branch("clean").is_err());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_state_override_applies_to_one_check() -> Result<(), eyre::Error> {
+        let (mut simulator, contract_address) = setup_local_erc20_test().await?;
+
+        let sender = Address::from_str("0x1000000000000000000000000000000000000001").unwrap();
+        let recipient = Address::from_str("0x2000000000000000000000000000000000000002").unwrap();
+        let amount = U256::from(100);
+        let transfer = || {
+            Call::new(
+                sender,
+                contract_address,
+                MockERC20::transferCall {
+                    to: AAddress::from_slice(recipient.as_slice()),
+                    amount,
+                }
+                .abi_encode(),
+                U256::ZERO,
+            )
+        };
+
+        // MockERC20 keeps balances in the mapping at slot 4
+        let mut preimage = [0u8; 64];
+        preimage[..32].copy_from_slice(sender.into_word().as_slice());
+        preimage[63] = 4;
+        let balance_slot = forge::revm::primitives::keccak256(preimage);
+        let state_override = StateOverride::from([(
+            contract_address,
+            AccountOverride {
+                state_diff: Some(HashMap::from([(
+                    balance_slot,
+                    amount.to_be_bytes::<32>().into(),
+                )])),
+                ..Default::default()
+            },
+        )]);
+
+        let report = simulator
+            .check_transaction_with_options(
+                transfer(),
+                CheckOptions {
+                    state_override,
+                    ..Default::default()
+                },
+            )
+            .await?;
+        assert!(report.succeeded());
+        assert!(report.requirements.is_empty());
+
+        // Gone for the next check
+        let report = simulator.check_transaction(transfer()).await?;
+        assert_eq!(report.requirements.len(), 1);
+        Ok(())
+    }
+
+    // Constructor: token.transfer(recipient, 100), bubbling up a revert
+    fn transfer_in_constructor(token: Address, recipient: Address) -> String {
+        format!(
+            "63a9059cbb60e01b60005273{}600452606460245260006000604460006000\
+             73{}5af16056573d600060003e3d6000fd5b00",
+            forge::revm::primitives::hex::encode(recipient),
+            forge::revm::primitives::hex::encode(token),
+        )
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_constructor_shortfall_is_attributed_to_deployer() -> Result<(), eyre::Error> {
+        let deployer = Address::repeat_byte(0xde);
+        let (mut simulator, contract_address) = setup_local_erc20_test_with(
+            AssetSimulator::builder()
+                .with_erc20_checker()
+                .with_account_of_interest(deployer),
+        )
+        .await?;
+        let recipient = Address::from_str("0x2000000000000000000000000000000000000002").unwrap();
+
+        let init_code = Bytes::from_str(&transfer_in_constructor(contract_address, recipient))?;
+        let report = simulator
+            .check_transaction(Call::create(deployer, init_code, U256::ZERO))
+            .await?;
+
+        assert!(report.succeeded());
+        assert_eq!(report.requirements.len(), 1);
+        let missing = &report.requirements[0];
+        assert_ne!(
+            missing.account, deployer,
+            "The new contract sends the tokens"
+        );
+        assert_eq!(missing.deployer, Some(deployer));
+        assert!(!missing.external);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_outflow_mode_attributes_constructor_to_deployer() -> Result<(), eyre::Error> {
+        let deployer = Address::repeat_byte(0xde);
+        let (mut simulator, contract_address) = setup_local_erc20_test_with(
+            AssetSimulator::builder()
+                .with_erc20_checker()
+                .with_account_of_interest(deployer),
+        )
+        .await?;
+        let recipient = Address::from_str("0x2000000000000000000000000000000000000002").unwrap();
+
+        let init_code = Bytes::from_str(&transfer_in_constructor(contract_address, recipient))?;
+        let report = simulator
+            .check_transaction_with_options(
+                Call::create(deployer, init_code, U256::ZERO),
+                CheckOptions {
+                    mode: DiscoveryMode::Outflow,
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        assert!(report.succeeded());
+        assert_eq!(report.requirements.len(), 1);
+        let missing = &report.requirements[0];
+        assert_ne!(missing.account, deployer);
+        assert_eq!(missing.deployer, Some(deployer));
+        assert!(!missing.external);
+        assert_eq!(
+            missing.required,
+            AssetSpec::ERC20 {
+                token: contract_address,
+                amount: U256::from(100)
+            }
+        );
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_outflow_mode_attributes_create2_to_sender() -> Result<(), eyre::Error> {
+        let deployer = Address::repeat_byte(0xde);
+        let factory = Address::repeat_byte(0xfa);
+        let (mut simulator, contract_address) = setup_local_erc20_test_with(
+            AssetSimulator::builder()
+                .with_erc20_checker()
+                .with_account_of_interest(deployer),
+        )
+        .await?;
+        let recipient = Address::from_str("0x2000000000000000000000000000000000000002").unwrap();
+
+        // Runtime: CREATE2 (salt 0) of the init code appended to it, bubbling
+        // up a revert
+        let factory_code = Bytes::from_str(&format!(
+            "605860216000396000605860006000f51561001657005b3d600060003e3d6000fd{}",
+            transfer_in_constructor(contract_address, recipient),
+        ))?;
+        simulator
+            .executor_mut()
+            .set_code(factory, Bytecode::new_raw(factory_code))?;
+
+        let report = simulator
+            .check_transaction_with_options(
+                Call::new(deployer, factory, Bytes::new(), U256::ZERO),
+                CheckOptions {
+                    mode: DiscoveryMode::Outflow,
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        // Neither the factory nor the sender holds anything; the sender pays
+        assert!(report.succeeded());
+        assert_eq!(report.requirements.len(), 1);
+        let missing = &report.requirements[0];
+        assert_ne!(missing.account, factory);
+        assert_eq!(missing.deployer, Some(deployer));
+        assert!(!missing.external);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_time_warp_finds_unlock_timestamp() -> Result<(), eyre::Error> {
+        let mut simulator = AssetSimulator::builder()
+            .with_block_timestamp(1_000)
+            .build()
+            .await?;
+
+        // Runtime: `if (block.timestamp < 1_000_000) revert();`, preceded by
+        // an init code that returns it
+        let init_code = Bytes::from_str(
+            "6011600c60003960116000f3\
+             63000f42404210600b57005b60006000fd",
+        )?;
+        let timelock = simulator
+            .executor_mut()
+            .deploy(Address::repeat_byte(4), init_code, U256::ZERO, None)?
+            .address;
+        let call = Call::new(Address::repeat_byte(1), timelock, Bytes::new(), U256::ZERO);
+
+        let report = simulator.check_transaction(call.clone()).await?;
+        assert_eq!(report.status, DiscoveryStatus::Reverted);
+        assert_eq!(report.time_requirement, None);
+
+        let report = simulator
+            .check_transaction_with_options(
+                call,
+                CheckOptions {
+                    time_warp: true,
+                    ..Default::default()
+                },
+            )
+            .await?;
+        assert!(report.succeeded());
+        assert_eq!(
+            report.time_requirement,
+            Some(TimeRequirement::NotBefore {
+                timestamp: 1_000_000
+            })
+        );
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_time_warp_ignores_changes_that_get_nowhere() -> Result<(), eyre::Error> {
+        let mut simulator = AssetSimulator::builder()
+            .with_block_timestamp(1_000)
+            .build()
+            .await?;
+        let options = CheckOptions {
+            time_warp: true,
+            ..Default::default()
+        };
+
+        // Runtime: `revert Late(block.timestamp)`; only the data after the
+        // selector changes from block to block
+        let late = Address::repeat_byte(0x1a);
+        simulator.executor_mut().set_code(
+            late,
+            Bytecode::new_raw(Bytes::from_str("63deadbeef60e01b6000524260045260246000fd")?),
+        )?;
+        let report = simulator
+            .check_transaction_with_options(
+                Call::new(Address::repeat_byte(1), late, Bytes::new(), U256::ZERO),
+                options.clone(),
+            )
+            .await?;
+        assert_eq!(report.status, DiscoveryStatus::Reverted);
+        assert_eq!(report.time_requirement, None);
+
+        // Runtime: `revert(block.timestamp < 1_000_000 ? 0xaaaaaaaa : 0xbbbbbbbb)`;
+        // past the gate it just fails another way
+        let gated = Address::repeat_byte(0x9a);
+        simulator.executor_mut().set_code(
+            gated,
+            Bytecode::new_raw(Bytes::from_str(
+                "620f4240421061001a5763bbbbbbbb60e01b60005260046000fd5b63aaaaaaaa60e0\
+                 1b60005260046000fd",
+            )?),
+        )?;
+        let report = simulator
+            .check_transaction_with_options(
+                Call::new(Address::repeat_byte(1), gated, Bytes::new(), U256::ZERO),
+                options,
+            )
+            .await?;
+        assert_eq!(report.status, DiscoveryStatus::Reverted);
+        assert_eq!(report.time_requirement, None);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_time_warp_tells_revert_reasons_apart() -> Result<(), eyre::Error> {
+        let (mut simulator, contract_address) = setup_local_erc20_test_with(
+            AssetSimulator::builder()
+                .with_erc20_checker()
+                .with_block_timestamp(1_000),
+        )
+        .await?;
+        let gate = Address::repeat_byte(0x9b);
+        let recipient = Address::repeat_byte(0x20);
+
+        // Runtime: `require(block.timestamp >= 1_000_000, "locked")`, then
+        // `token.transfer(to, 100)` with `(token, to)` as calldata, bubbling
+        // up a revert. Both reverts are an `Error(string)`.
+        simulator.executor_mut().set_code(
+            gate,
+            Bytecode::new_raw(Bytes::from_str(
+                "620f424042106100405763a9059cbb60e01b6000526020356004526064602452600060\
+                 006044600060006000355af11561003557005b3d600060003e3d6000fd5b6308c379a0\
+                 60e01b60005260206004526006602452656c6f636b656460d01b60445260646000fd",
+            )?),
+        )?;
+        let call = Call::new(
+            Address::repeat_byte(1),
+            gate,
+            [
+                contract_address.into_word().to_vec(),
+                recipient.into_word().to_vec(),
+            ]
+            .concat(),
+            U256::ZERO,
+        );
+
+        let report = simulator
+            .check_transaction_with_options(
+                call,
+                CheckOptions {
+                    time_warp: true,
+                    ..Default::default()
+                },
+            )
+            .await?;
+        assert!(report.succeeded());
+        assert_eq!(
+            report.time_requirement,
+            Some(TimeRequirement::NotBefore {
+                timestamp: 1_000_000
+            })
+        );
+        assert_eq!(report.requirements.len(), 1);
+        assert_eq!(report.requirements[0].account, gate);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_unresolved_revert_is_classified() -> Result<(), eyre::Error> {
+        let mut simulator = AssetSimulator::builder().build().await?;
+
+        // Runtime: `revert EnforcedPause();`, preceded by an init code that
+        // returns it
+        let init_code = Bytes::from_str(
+            "6010600c60003960106000f3\
+             63d93c066560e01b60005260046000fd",
+        )?;
+        let paused = simulator
+            .executor_mut()
+            .deploy(Address::repeat_byte(4), init_code, U256::ZERO, None)?
+            .address;
+
+        let report = simulator
+            .check_transaction(Call::new(
+                Address::repeat_byte(1),
+                paused,
+                Bytes::new(),
+                U256::ZERO,
+            ))
+            .await?;
+        assert!(!report.succeeded());
+        assert!(report.requirements.is_empty());
+        assert_eq!(report.blocker, Some(BlockerCategory::Paused));
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_open_shortfall_is_not_a_blocker() -> Result<(), eyre::Error> {
+        let (mut simulator, contract_address) = setup_local_erc20_test().await?;
+
+        let sender = Address::from_str("0x1000000000000000000000000000000000000001").unwrap();
+        let recipient = Address::from_str("0x2000000000000000000000000000000000000002").unwrap();
+        let transfer_call = Call::new(
+            sender,
+            contract_address,
+            MockERC20::transferCall {
+                to: AAddress::from_slice(recipient.as_slice()),
+                amount: U256::from(100),
+            }
+            .abi_encode(),
+            U256::ZERO,
+        );
+
+        // Nothing was dealt, so the revert is just the shortfall
+        let report = simulator
+            .check_transaction_with_options(
+                transfer_call,
+                CheckOptions {
+                    auto_fix: false,
+                    ..Default::default()
+                },
+            )
+            .await?;
+        assert_eq!(report.status, DiscoveryStatus::Reverted);
+        assert_eq!(report.requirements.len(), 1);
+        assert_eq!(report.blocker, None);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_checks_are_isolated() -> Result<(), eyre::Error> {
+        let (mut simulator, contract_address) = setup_local_erc20_test().await?;
+
+        let sender = Address::from_str("0x1000000000000000000000000000000000000001").unwrap();
+        let recipient = Address::from_str("0x2000000000000000000000000000000000000002").unwrap();
+        let amount = U256::from(100);
+        let transfer = || {
+            Call::new(
+                sender,
+                contract_address,
+                MockERC20::transferCall {
+                    to: AAddress::from_slice(recipient.as_slice()),
+                    amount,
+                }
+                .abi_encode(),
+                U256::ZERO,
+            )
+        };
+
+        // The first check's deal is gone by the second one
+        let first = simulator.check_transaction(transfer()).await?;
+        let second = simulator.check_transaction(transfer()).await?;
+        assert_eq!(first.requirements.len(), 1);
+        assert_eq!(second.requirements.len(), 1);
+        assert_eq!(second.requirements[0].current_balance, U256::ZERO);
+
+        // Unless asked to keep it
+        simulator
+            .check_transaction_with_options(
+                transfer(),
+                CheckOptions {
+                    keep_state: true,
+                    ..Default::default()
+                },
+            )
+            .await?;
+        let after_keep = simulator.check_transaction(transfer()).await?;
+        assert!(after_keep.requirements.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_allowance_issue_not_balance_issue() -> Result<(), eyre::Error> {
+        let (mut simulator, contract_address) = setup_local_erc20_test().await?;
+
+        let sender = Address::from_str("0x1000000000000000000000000000000000000001").unwrap();
+        let recipient = Address::from_str("0x2000000000000000000000000000000000000002").unwrap();
+        let spender = Address::from_str("0x3000000000000000000000000000000000000003").unwrap();
+        let minter = Address::repeat_byte(4);
+        let amount = U256::from(100);
+
+        // Mint tokens to sender (so balance is sufficient)
+        mint_tokens(
+            &mut simulator,
+            contract_address,
+            minter,
+            sender,
+            amount * U256::from(2),
+        )
+        .await?;
+
+        // Attempt transferFrom without approval - this should revert due to allowance, not balance
+        let transfer_from_call = Call::new(
+            spender, // Spender trying to transfer from sender
+            contract_address,
+            MockERC20::transferFromCall {
+                from: AAddress::from_slice(sender.as_slice()),
+                to: AAddress::from_slice(recipient.as_slice()),
+                amount, // Amount that sender has, but spender is not approved for
+            }
+            .abi_encode(),
+            U256::ZERO,
+        );
+
+        let result = simulator
+            .check_transaction(transfer_from_call)
+            .await?
+            .requirements;
+        // This should return empty because the revert is due to missing allowance, not insufficient balance
+        // The ERC20Checker should only identify balance issues, not allowance issues
+        assert!(
+            result.is_empty(),
+            "Should not detect balance issues when problem is allowance"
+        );
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_missing_allowance_detected_and_dealt() -> Result<(), eyre::Error> {
+        let (mut simulator, contract_address) = setup_local_erc20_test_with(
+            AssetSimulator::builder()
+                .with_erc20_checker()
+                .with_allowance_checker(),
+        )
+        .await?;
+
+        let sender = Address::from_str("0x1000000000000000000000000000000000000001").unwrap();
+        let recipient = Address::from_str("0x2000000000000000000000000000000000000002").unwrap();
+        let spender = Address::from_str("0x3000000000000000000000000000000000000003").unwrap();
+        let minter = Address::repeat_byte(4);
+        let amount = U256::from(100);
+
+        mint_tokens(&mut simulator, contract_address, minter, sender, amount).await?;
+
+        let calldata = MockERC20::transferFromCall {
+            from: AAddress::from_slice(sender.as_slice()),
+            to: AAddress::from_slice(recipient.as_slice()),
+            amount,
+        }
+        .abi_encode();
+        let transfer_from_call = Call::new(spender, contract_address, calldata.clone(), U256::ZERO);
+
+        let result = simulator
+            .check_transaction_with_options(
+                transfer_from_call,
+                CheckOptions {
+                    keep_state: true,
+                    ..Default::default()
+                },
+            )
+            .await?
+            .requirements;
+        assert_eq!(result.len(), 1, "Only the allowance should be missing");
+        let asset = &result[0];
+        assert_eq!(asset.account, sender);
+        assert_eq!(asset.kind, RequirementKind::Allowance { spender });
+        assert_eq!(asset.current_balance, U256::ZERO);
+        assert_eq!(asset.missing_amount, amount);
+
+        // The dealt allowance lets the transfer through
+        let result = simulator.executor_mut().transact_raw(
+            spender,
+            contract_address,
+            calldata.into(),
+            U256::ZERO,
+        )?;
+        assert!(
+            !result.exit_reason.is_revert(),
+            "transferFrom should succeed"
+        );
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_transfer_from_self_needs_allowance() -> Result<(), eyre::Error> {
+        let (mut simulator, contract_address) = setup_local_erc20_test_with(
+            AssetSimulator::builder()
+                .with_erc20_checker()
+                .with_allowance_checker(),
+        )
+        .await?;
+
+        let sender = Address::from_str("0x1000000000000000000000000000000000000001").unwrap();
+        let recipient = Address::from_str("0x2000000000000000000000000000000000000002").unwrap();
+        let amount = U256::from(100);
+
+        // Like OpenZeppelin's ERC20, the mock spends the owner's allowance to
+        // itself as well
+        let calldata = MockERC20::transferFromCall {
+            from: AAddress::from_slice(sender.as_slice()),
+            to: AAddress::from_slice(recipient.as_slice()),
+            amount,
+        }
+        .abi_encode();
+        let report = simulator
+            .check_transaction(Call::new(sender, contract_address, calldata, U256::ZERO))
+            .await?;
 
-        Ok((simulator, contract_address))
+        assert!(report.succeeded());
+        let mut kinds: Vec<_> = report
+            .requirements
+            .iter()
+            .map(|missing| (missing.account, missing.kind, missing.missing_amount))
+            .collect();
+        kinds.sort();
+        assert_eq!(
+            kinds,
+            vec![
+                (sender, RequirementKind::Balance, amount),
+                (
+                    sender,
+                    RequirementKind::Allowance { spender: sender },
+                    amount
+                ),
+            ]
+        );
+        Ok(())
     }
 
     #[tokio::test(flavor = "multi_thread")]
-    async fn test_successful_transfer_returns_empty() -> Result<(), eyre::Error> {
+    async fn test_shortfall_of_other_account_is_external() -> Result<(), eyre::Error> {
         let (mut simulator, contract_address) = setup_local_erc20_test().await?;
 
         let sender = Address::from_str("0x1000000000000000000000000000000000000001").unwrap();
         let recipient = Address::from_str("0x2000000000000000000000000000000000000002").unwrap();
-        let minter = Address::repeat_byte(4);
+        let user = Address::from_str("0x5000000000000000000000000000000000000005").unwrap();
         let amount = U256::from(100);
 
-        // Mint tokens to sender
-        mint_tokens(
-            &mut simulator,
-            contract_address,
-            minter,
-            sender,
-            amount * U256::from(2),
-        )
-        .await?;
+        // Only `user` matters; the sender is a third party
+        simulator.set_accounts_of_interest([user]);
 
-        // Test a successful transfer
         let transfer_call = Call::new(
             sender,
             contract_address,
@@ -262,98 +2673,796 @@ mod tests {
             U256::ZERO,
         );
 
-        let result = simulator.check_transaction(transfer_call).await?;
-        assert!(
-            result.is_empty(),
-            "Successful transfer should return no missing assets"
-        );
+        let result = simulator
+            .check_transaction(transfer_call)
+            .await?
+            .requirements;
+        assert_eq!(result.len(), 1, "Should report the sender's shortfall");
+        assert_eq!(result[0].account, sender);
+        assert!(result[0].external, "Sender is not an account of interest");
+
+        // External blockers must never be dealt
+        let balance = simulator.executor_mut().call_raw(
+            Address::ZERO,
+            contract_address,
+            MockERC20::balanceOfCall {
+                account: AAddress::from_slice(sender.as_slice()),
+            }
+            .abi_encode()
+            .into(),
+            U256::ZERO,
+        )?;
+        let balance = MockERC20::balanceOfCall::abi_decode_returns(&balance.result)?;
+        assert_eq!(balance, U256::ZERO);
         Ok(())
     }
 
     #[tokio::test(flavor = "multi_thread")]
-    async fn test_insufficient_balance_detected() -> Result<(), eyre::Error> {
+    async fn test_deal_leaves_allowance_untouched() -> Result<(), eyre::Error> {
         let (mut simulator, contract_address) = setup_local_erc20_test().await?;
 
         let sender = Address::from_str("0x1000000000000000000000000000000000000001").unwrap();
         let recipient = Address::from_str("0x2000000000000000000000000000000000000002").unwrap();
+        let spender = Address::from_str("0x3000000000000000000000000000000000000003").unwrap();
         let amount = U256::from(100);
 
-        // Test transfer with insufficient balance (sender has 0 tokens in this fresh simulator)
-        let transfer_call = Call::new(
+        // Sender approves exactly `amount` but holds no tokens
+        let approve = simulator.executor_mut().transact_raw(
             sender,
             contract_address,
-            MockERC20::transferCall {
+            MockERC20::approveCall {
+                spender: AAddress::from_slice(spender.as_slice()),
+                amount,
+            }
+            .abi_encode()
+            .into(),
+            U256::ZERO,
+        )?;
+        assert!(!approve.reverted, "Approve should succeed");
+
+        // transferFrom reads the allowance slot before failing on balance
+        let transfer_from_call = Call::new(
+            spender,
+            contract_address,
+            MockERC20::transferFromCall {
+                from: AAddress::from_slice(sender.as_slice()),
                 to: AAddress::from_slice(recipient.as_slice()),
                 amount,
             }
             .abi_encode(),
             U256::ZERO,
         );
+        let result = simulator
+            .check_transaction_with_options(
+                transfer_from_call,
+                CheckOptions {
+                    keep_state: true,
+                    ..Default::default()
+                },
+            )
+            .await?
+            .requirements;
+        assert_eq!(result.len(), 1, "Should detect the missing balance");
+
+        // Only the balance slot was dealt: the allowance was consumed normally
+        let allowance = simulator.executor_mut().call_raw(
+            Address::ZERO,
+            contract_address,
+            MockERC20::allowanceCall {
+                owner: AAddress::from_slice(sender.as_slice()),
+                spender: AAddress::from_slice(spender.as_slice()),
+            }
+            .abi_encode()
+            .into(),
+            U256::ZERO,
+        )?;
+        let allowance = MockERC20::allowanceCall::abi_decode_returns(&allowance.result)?;
+        assert_eq!(allowance, U256::ZERO);
+        Ok(())
+    }
 
-        // Use no_fix version to just detect without fixing
-        let result = simulator.check_transaction(transfer_call).await?;
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_erc721_deal_moves_owners_and_balances() -> Result<(), eyre::Error> {
+        let mut simulator = AssetSimulator::builder()
+            .with_erc721_checker()
+            .build()
+            .await?;
+        let deployer = Address::repeat_byte(4);
+        let previous = Address::repeat_byte(0xee);
+        let alice = Address::repeat_byte(0xa1);
+        let bob = Address::repeat_byte(0xb0);
+
+        // Minimal ERC-721 keeping owners, balances, approvals and operators
+        // in mappings at slots 2 to 5; its constructor mints ids 1 and 2 to
+        // `previous`
+        let nft_code = Bytes::from_str(
+            "73eeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeee600160005260026020526040600020\
+             5573eeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeee6002600052600260205260406000\
+             2055600273eeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeee6000526003602052604060\
+             00205561024f8061007c6000396000f360003560e01c806301ffc9a71461006457806363\
+             52211e1461008257806370a08231146100a2578063081812fc146100bc578063e985e9c5\
+             146100d6578063a22cb465146100fe578063095ea7b31461012057806323b872dd146101\
+             6c575b60006000fd5b60043560e01c806380ac58cd14906301ffc9a71417600052602060\
+             00f35b6004356000526002602052604060002054801561005e5760005260206000f35b60\
+             0435600052600360205260406000205460005260206000f35b6004356000526004602052\
+             60406000205460005260206000f35b600435600052600560205260406000206024356000\
+             5260205260406000205460005260206000f35b6024353360005260056020526040600020\
+             600435600052602052604060002055005b60243560005260026020526040600020548033\
+             14816000526005602052604060002033600052602052604060002054171561005e575060\
+             04356024356000526004602052604060002055005b604435600052600260205260406000\
+             2054600435801561005e57141561005e5760043533146044356000526004602052604060\
+             002054331417600435600052600560205260406000203360005260205260406000205417\
+             1561005e5760006044356000526004602052604060002055600435600052600360205260\
+             406000208054600190039055602435600052600360205260406000208054600101905560\
+             243560443560005260026020526040600020556044356024356004357fddf252ad1be2c8\
+             9b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef60006000a400",
+        )?;
+        let nft = simulator
+            .executor_mut()
+            .deploy(deployer, nft_code, U256::ZERO, None)?
+            .address;
+
+        // Runtime: `nft.transferFrom(msg.sender, to, id)` for ids 1 and 2,
+        // with `(nft, to)` as calldata, bubbling up a revert
+        let relay_code = Bytes::from_str(
+            "6100538061000d6000396000f36323b872dd60e01b600052336004526020356024526001\
+             604452600060006064600060006000355af1156100485760026044526000600060646000\
+             60006000355af11561004857005b3d600060003e3d6000fd",
+        )?;
+        let relay = simulator
+            .executor_mut()
+            .deploy(deployer, relay_code, U256::ZERO, None)?
+            .address;
+        let move_both = Call::new(
+            alice,
+            relay,
+            [nft.into_word().to_vec(), bob.into_word().to_vec()].concat(),
+            U256::ZERO,
+        );
 
-        assert!(!result.is_empty(), "Should detect missing balance");
+        fn owner_of(simulator: &mut AssetSimulator, nft: Address, id: u64) -> Address {
+            let owner = simulator
+                .executor_mut()
+                .call_raw(
+                    Address::ZERO,
+                    nft,
+                    MockERC721::ownerOfCall {
+                        tokenId: U256::from(id),
+                    }
+                    .abi_encode()
+                    .into(),
+                    U256::ZERO,
+                )
+                .unwrap();
+            let owner = MockERC721::ownerOfCall::abi_decode_returns(&owner.result).unwrap();
+            Address::from_slice(owner.as_slice())
+        }
+        fn balance_of(simulator: &mut AssetSimulator, nft: Address, owner: Address) -> U256 {
+            let balance = simulator
+                .executor_mut()
+                .call_raw(
+                    Address::ZERO,
+                    nft,
+                    MockERC721::balanceOfCall {
+                        owner: AAddress::from_slice(owner.as_slice()),
+                    }
+                    .abi_encode()
+                    .into(),
+                    U256::ZERO,
+                )
+                .unwrap();
+            MockERC721::balanceOfCall::abi_decode_returns(&balance.result).unwrap()
+        }
 
-        if let Some(asset) = result.first() {
-            assert_eq!(asset.account, sender);
-            assert_eq!(asset.missing_amount, amount);
-            assert_eq!(asset.current_balance, U256::ZERO);
+        // Each id is found missing in its own round
+        let report = simulator.check_transaction(move_both.clone()).await?;
+        assert!(report.succeeded());
+        assert_eq!(report.requirements.len(), 1);
+        assert_eq!(report.requirements[0].account, alice);
+        assert_eq!(report.requirements[0].kind, RequirementKind::Ownership);
+        assert_eq!(
+            report.requirements[0].required,
+            AssetSpec::ERC721 {
+                token: nft,
+                token_ids: vec![U256::from(1), U256::from(2)]
+            }
+        );
+
+        // Dealing reassigns the owners and moves both balance counters
+        simulator.apply_grants(vec![AssetGrant::erc721(
+            alice,
+            nft,
+            vec![U256::from(1), U256::from(2)],
+        )])?;
+        assert_eq!(owner_of(&mut simulator, nft, 1), alice);
+        assert_eq!(owner_of(&mut simulator, nft, 2), alice);
+        assert_eq!(balance_of(&mut simulator, nft, previous), U256::ZERO);
+        assert_eq!(balance_of(&mut simulator, nft, alice), U256::from(2));
+
+        // Owning both, alice still has to let the relay move them
+        let report = simulator
+            .check_transaction_with_options(
+                move_both,
+                CheckOptions {
+                    keep_state: true,
+                    ..Default::default()
+                },
+            )
+            .await?;
+        assert!(report.succeeded());
+        assert_eq!(report.requirements.len(), 1);
+        assert_eq!(
+            report.requirements[0].kind,
+            RequirementKind::Approval { operator: relay }
+        );
+        assert_eq!(owner_of(&mut simulator, nft, 1), bob);
+        assert_eq!(owner_of(&mut simulator, nft, 2), bob);
+        assert_eq!(balance_of(&mut simulator, nft, alice), U256::ZERO);
+        assert_eq!(balance_of(&mut simulator, nft, bob), U256::from(2));
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_erc1155_shortfalls_are_reported_per_id() -> Result<(), eyre::Error> {
+        let mut simulator = AssetSimulator::builder()
+            .with_erc1155_checker()
+            .build()
+            .await?;
+        let alice = Address::repeat_byte(0xa1);
+        let bob = Address::repeat_byte(0xb0);
+
+        // Minimal ERC-1155 keeping `balances[id][account]` at slot 0 and
+        // operators at slot 1, without `balanceOfBatch`; its constructor
+        // gives alice 2 of id 1
+        let token_code = Bytes::from_str(
+            "600260016000526000602052604060002073a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1\
+             a1a16000526020526040600020556101fb8061003f6000396000f360003560e01c806301\
+             ffc9a71461004d578062fdd58e1461006b578063e985e9c514610093578063a22cb46514\
+             6100bb578063f242432a146100dd5780632eb2c2d614610113575b60006000fd5b600435\
+             60e01c8063d9b67a2614906301ffc9a7141760005260206000f35b602435600052600060\
+             2052604060002060043560005260205260406000205460005260206000f35b6004356000\
+             526001602052604060002060243560005260205260406000205460005260206000f35b60\
+             24353360005260016020526040600020600435600052602052604060002055005b600435\
+             331460043560005260016020526040600020336000526020526040600020541715610047\
+             57610174604435606435610176565b600435331460043560005260016020526040600020\
+             3360005260205260406000205417156100475760005b6044356004013581101561017457\
+             61016c816020026044356024010135826020026064356024010135610176565b60010161\
+             013e565b005b816000526000602052604060002060043560005260205260406000208054\
+             828110610047578290039055816000526000602052604060002060243560005260205260\
+             406000208054820190558160005280602052602435600435337fc3d58168c5ae7397731d\
+             063d5bbf3d657854427343f4c083240f7aacaa2d0f6260406000a4505056",
+        )?;
+        let token = simulator
+            .executor_mut()
+            .deploy(Address::repeat_byte(4), token_code, U256::ZERO, None)?
+            .address;
+
+        fn balance_of(
+            simulator: &mut AssetSimulator,
+            token: Address,
+            account: Address,
+            id: u64,
+        ) -> U256 {
+            let balance = simulator
+                .executor_mut()
+                .call_raw(
+                    Address::ZERO,
+                    token,
+                    IERC1155::balanceOfCall {
+                        account: AAddress::from_slice(account.as_slice()),
+                        id: U256::from(id),
+                    }
+                    .abi_encode()
+                    .into(),
+                    U256::ZERO,
+                )
+                .unwrap();
+            IERC1155::balanceOfCall::abi_decode_returns(&balance.result).unwrap()
+        }
 
+        let batch = Call::new(
+            alice,
+            token,
+            IERC1155::safeBatchTransferFromCall {
+                from: AAddress::from_slice(alice.as_slice()),
+                to: AAddress::from_slice(bob.as_slice()),
+                ids: vec![U256::from(1), U256::from(2)],
+                values: vec![U256::from(5), U256::from(7)],
+                data: Default::default(),
+            }
+            .abi_encode(),
+            U256::ZERO,
+        );
+        let report = simulator.check_transaction(batch).await?;
+        assert!(report.succeeded());
+
+        // Each short id is its own requirement with its own balance
+        assert_eq!(report.requirements.len(), 2);
+        for (requirement, (id, amount, balance)) in
+            report.requirements.iter().zip([(1, 5, 2), (2, 7, 0)])
+        {
+            assert_eq!(requirement.account, alice);
             assert_eq!(
-                asset.required,
-                AssetSpec::ERC20 {
-                    token: contract_address,
-                    amount
+                requirement.required,
+                AssetSpec::ERC1155 {
+                    token,
+                    token_amounts: HashMap::from([(U256::from(id), U256::from(amount))]),
                 }
             );
+            assert_eq!(requirement.current_balance, U256::from(balance));
+            assert_eq!(requirement.missing_amount, U256::from(amount - balance));
         }
+
+        // Dealing patches the nested mapping entry of each id
+        simulator.apply_grants(vec![AssetGrant::erc1155(
+            alice,
+            token,
+            HashMap::from([
+                (U256::from(1), U256::from(10)),
+                (U256::from(2), U256::from(4)),
+            ]),
+        )])?;
+        assert_eq!(balance_of(&mut simulator, token, alice, 1), U256::from(10));
+        assert_eq!(balance_of(&mut simulator, token, alice, 2), U256::from(4));
+        assert_eq!(balance_of(&mut simulator, token, bob, 1), U256::ZERO);
         Ok(())
     }
 
     #[tokio::test(flavor = "multi_thread")]
-    async fn test_allowance_issue_not_balance_issue() -> Result<(), eyre::Error> {
+    async fn test_rejected_permits_are_diagnosed() -> Result<(), eyre::Error> {
+        let (mut simulator, token) = setup_permit_token_test().await?;
+        let owner = Address::from_str(PERMIT_OWNER)?;
+        let spender = Address::repeat_byte(0x5e);
+        let relayer = Address::repeat_byte(0x3e);
+
+        let cases = [
+            (999, PERMIT_SIGNATURE, PermitFailure::ExpiredDeadline),
+            (
+                2_000,
+                PERMIT_SIGNATURE_NONCE_1,
+                PermitFailure::NonceMismatch,
+            ),
+            (
+                2_000,
+                PERMIT_SIGNATURE_OTHER_KEY,
+                PermitFailure::InvalidSignature,
+            ),
+        ];
+        for (deadline, signature, reason) in cases {
+            let call = Call::new(
+                relayer,
+                token,
+                permit(spender, deadline, signature),
+                U256::ZERO,
+            );
+            let report = simulator.check_transaction(call).await?;
+            assert_eq!(report.status, DiscoveryStatus::Reverted);
+            assert_eq!(report.requirements.len(), 1);
+            assert_eq!(report.requirements[0].account, owner);
+            assert_eq!(
+                report.requirements[0].kind,
+                RequirementKind::InvalidPermit { spender, reason }
+            );
+        }
+
+        // In time, over the right nonce and by the owner it goes through
+        let call = Call::new(
+            relayer,
+            token,
+            permit(spender, 2_000, PERMIT_SIGNATURE),
+            U256::ZERO,
+        );
+        let report = simulator.check_transaction(call).await?;
+        assert!(report.succeeded());
+        assert!(report.requirements.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_permit_then_transfer_from_charges_owner() -> Result<(), eyre::Error> {
+        let (mut simulator, token) = setup_permit_token_test().await?;
+        let owner = Address::from_str(PERMIT_OWNER)?;
+        let relay = Address::repeat_byte(0x5e);
+        let recipient = Address::repeat_byte(0xb0);
+
+        // Runtime: `token.permit(...)`, then `token.transferFrom(owner, to, value)`,
+        // with `(token, permit arguments, to)` as calldata, bubbling up reverts
+        let relay_code = Bytes::from_str(
+            "63d505accf60e01b60005260e060206004376000600060e4600060006000355af1156100\
+             59576323b872dd60e01b6000526020356004526101003560245260603560445260006000\
+             6064600060006000355af11561005957005b3d600060003e3d6000fd",
+        )?;
+        simulator
+            .executor_mut()
+            .set_code(relay, Bytecode::new_raw(relay_code))?;
+
+        let data = [
+            token.into_word().to_vec(),
+            permit(relay, 2_000, PERMIT_SIGNATURE)[4..].to_vec(),
+            recipient.into_word().to_vec(),
+        ]
+        .concat();
+        let report = simulator
+            .check_transaction(Call::new(
+                Address::repeat_byte(0x3e),
+                relay,
+                data,
+                U256::ZERO,
+            ))
+            .await?;
+
+        // The permit is fine; its owner lacks the tokens it lets the relay pull
+        assert!(report.succeeded());
+        assert_eq!(report.requirements.len(), 1);
+        let missing = &report.requirements[0];
+        assert_eq!(missing.account, owner);
+        assert_eq!(missing.kind, RequirementKind::Balance);
+        assert_eq!(
+            missing.required,
+            AssetSpec::ERC20 {
+                token,
+                amount: U256::from(100)
+            }
+        );
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_rejected_authorizations_are_diagnosed() -> Result<(), eyre::Error> {
+        let (mut simulator, token) = setup_permit_token_test().await?;
+        let relayer = Address::repeat_byte(0x3e);
+
+        let cases = [
+            (
+                false,
+                1_000,
+                2_000,
+                AUTHORIZATION_SIGNATURE,
+                AuthorizationFailure::NotYetValid,
+            ),
+            (
+                false,
+                0,
+                1_000,
+                AUTHORIZATION_SIGNATURE,
+                AuthorizationFailure::Expired,
+            ),
+            (
+                true,
+                0,
+                2_000,
+                AUTHORIZATION_SIGNATURE,
+                AuthorizationFailure::CallerNotPayee,
+            ),
+            (
+                false,
+                0,
+                2_000,
+                AUTHORIZATION_SIGNATURE_OTHER_KEY,
+                AuthorizationFailure::InvalidSignature,
+            ),
+        ];
+        for (receive, valid_after, valid_before, signature, reason) in cases {
+            let data = authorization(receive, valid_after, valid_before, signature);
+            let report = simulator
+                .check_transaction(Call::new(relayer, token, data, U256::ZERO))
+                .await?;
+            assert_eq!(report.status, DiscoveryStatus::Reverted);
+            assert_eq!(report.requirements.len(), 1);
+            assert_eq!(
+                report.requirements[0].kind,
+                RequirementKind::InvalidAuthorization { reason }
+            );
+        }
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_authorization_charges_signer_then_is_spent() -> Result<(), eyre::Error> {
+        let (mut simulator, token) = setup_permit_token_test().await?;
+        let owner = Address::from_str(PERMIT_OWNER)?;
+        let relayer = Address::repeat_byte(0x3e);
+        let transfer = || {
+            Call::new(
+                relayer,
+                token,
+                authorization(false, 0, 2_000, AUTHORIZATION_SIGNATURE),
+                U256::ZERO,
+            )
+        };
+
+        // A valid authorization without funds: the signer pays, not the relayer
+        let report = simulator
+            .check_transaction_with_options(
+                transfer(),
+                CheckOptions {
+                    keep_state: true,
+                    ..Default::default()
+                },
+            )
+            .await?;
+        assert!(report.succeeded());
+        assert_eq!(report.requirements.len(), 1);
+        let missing = &report.requirements[0];
+        assert_eq!(missing.account, owner);
+        assert_eq!(missing.kind, RequirementKind::Balance);
+        assert_eq!(
+            missing.required,
+            AssetSpec::ERC20 {
+                token,
+                amount: U256::from(50)
+            }
+        );
+
+        // Submitting it again finds its nonce used
+        let report = simulator.check_transaction(transfer()).await?;
+        assert_eq!(report.status, DiscoveryStatus::Reverted);
+        assert_eq!(report.requirements.len(), 1);
+        assert_eq!(report.requirements[0].account, owner);
+        assert_eq!(
+            report.requirements[0].kind,
+            RequirementKind::InvalidAuthorization {
+                reason: AuthorizationFailure::NonceUsed
+            }
+        );
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_nested_value_transfer_out_of_funds() -> Result<(), eyre::Error> {
+        let mut simulator = AssetSimulator::builder()
+            .with_native_checker()
+            .build()
+            .await?;
+
+        let deployer = Address::repeat_byte(4);
+        let sender = Address::from_str("0x1000000000000000000000000000000000000001").unwrap();
+
+        // Forwarder that sends 100 wei to 0x2000…0002 and reverts if the CALL fails:
+        //   CALL(gas, recipient, 100, 0, 0, 0, 0); if iszero → revert
+        let bytecode = Bytes::from_str(concat!(
+            "61002b80600c6000396000f3",
+            "60006000600060006064",
+            "732000000000000000000000000000000000000002",
+            "5af115602657005b600080fd"
+        ))?;
+        let forwarder = simulator
+            .executor_mut()
+            .deploy(deployer, bytecode, U256::ZERO, None)?
+            .address;
+
+        let call = Call::new(sender, forwarder, Bytes::new(), U256::ZERO);
+        let result = simulator
+            .check_transaction_with_options(
+                call,
+                CheckOptions {
+                    keep_state: true,
+                    ..Default::default()
+                },
+            )
+            .await?
+            .requirements;
+
+        assert_eq!(
+            result.len(),
+            1,
+            "Should detect the forwarder's ETH shortfall"
+        );
+        let asset = &result[0];
+        assert_eq!(asset.account, forwarder);
+        assert_eq!(asset.required, AssetSpec::Native(U256::from(100)));
+        assert_eq!(asset.missing_amount, U256::from(100));
+
+        // The deal must have let the forwarder pay out
+        assert_eq!(simulator.executor_mut().get_balance(forwarder)?, U256::ZERO);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_value_above_sender_balance_is_dealt() -> Result<(), eyre::Error> {
+        let mut simulator = AssetSimulator::builder()
+            .with_native_checker()
+            .build()
+            .await?;
+
+        let sender = Address::from_str("0x1000000000000000000000000000000000000001").unwrap();
+        let recipient = Address::from_str("0x2000000000000000000000000000000000000002").unwrap();
+        let value = U256::from(10).pow(U256::from(18)); // 1 ETH
+
+        // revm rejects this before execution: the sender holds no ETH at all
+        let call = Call::new(sender, recipient, Bytes::new(), value);
+        let result = simulator
+            .check_transaction_with_options(
+                call,
+                CheckOptions {
+                    keep_state: true,
+                    ..Default::default()
+                },
+            )
+            .await?
+            .requirements;
+
+        assert_eq!(result.len(), 1, "Should report the upfront native cost");
+        assert_eq!(result[0].account, sender);
+        assert_eq!(result[0].required, AssetSpec::Native(value));
+        assert_eq!(result[0].missing_amount, value);
+
+        // After dealing, the retried transfer went through
+        assert_eq!(simulator.executor_mut().get_balance(recipient)?, value);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_pinned_nonce_survives_reverted_runs() -> Result<(), eyre::Error> {
         let (mut simulator, contract_address) = setup_local_erc20_test().await?;
 
         let sender = Address::from_str("0x1000000000000000000000000000000000000001").unwrap();
         let recipient = Address::from_str("0x2000000000000000000000000000000000000002").unwrap();
-        let spender = Address::from_str("0x3000000000000000000000000000000000000003").unwrap();
-        let minter = Address::repeat_byte(4);
-        let amount = U256::from(100);
 
-        // Mint tokens to sender (so balance is sufficient)
-        mint_tokens(
-            &mut simulator,
-            contract_address,
-            minter,
+        // The first run reverts for lack of tokens; the re-run after the
+        // deal must still be valid at nonce 0
+        let call = Call::new(
             sender,
-            amount * U256::from(2),
-        )
-        .await?;
-
-        // Attempt transferFrom without approval - this should revert due to allowance, not balance
-        let transfer_from_call = Call::new(
-            spender, // Spender trying to transfer from sender
             contract_address,
-            MockERC20::transferFromCall {
-                from: AAddress::from_slice(sender.as_slice()),
+            MockERC20::transferCall {
                 to: AAddress::from_slice(recipient.as_slice()),
-                amount, // Amount that sender has, but spender is not approved for
+                amount: U256::from(100),
             }
             .abi_encode(),
             U256::ZERO,
+        )
+        .with_nonce(0);
+        let report = simulator.check_transaction(call).await?;
+
+        assert!(report.succeeded());
+        assert_eq!(report.iterations.len(), 2);
+        assert_eq!(report.requirements.len(), 1);
+        assert_eq!(report.requirements[0].account, sender);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_envelope_fees_count_towards_upfront_cost() -> Result<(), eyre::Error> {
+        let mut simulator = AssetSimulator::builder()
+            .with_native_checker()
+            .build()
+            .await?;
+
+        let sender = Address::from_str("0x1000000000000000000000000000000000000001").unwrap();
+        let recipient = Address::from_str("0x2000000000000000000000000000000000000002").unwrap();
+        let value = U256::from(1_000);
+
+        // The sender must cover the max fee, not what is eventually paid
+        let call = Call::new(sender, recipient, Bytes::new(), value)
+            .with_gas_limit(21_000)
+            .with_fees(U256::from(10), U256::from(1));
+        let report = simulator.check_transaction(call).await?;
+
+        assert!(report.succeeded());
+        assert_eq!(report.requirements.len(), 1);
+        assert_eq!(
+            report.requirements[0].required,
+            AssetSpec::Native(U256::from(21_000 * 10) + value)
         );
+        Ok(())
+    }
 
-        let result = simulator.check_transaction(transfer_from_call).await?;
-        // This should return empty because the revert is due to missing allowance, not insufficient balance
-        // The ERC20Checker should only identify balance issues, not allowance issues
-        assert!(
-            result.is_empty(),
-            "Should not detect balance issues when problem is allowance"
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_outflow_mode_counts_gas_fees() -> Result<(), eyre::Error> {
+        let mut simulator = AssetSimulator::builder()
+            .with_native_checker()
+            .build()
+            .await?;
+
+        let sender = Address::from_str("0x1000000000000000000000000000000000000001").unwrap();
+        let recipient = Address::from_str("0x2000000000000000000000000000000000000002").unwrap();
+        let value = U256::from(1_000);
+
+        // Only the value is a transfer; the fee is paid outside of any frame
+        let call = Call::new(sender, recipient, Bytes::new(), value)
+            .with_gas_limit(21_000)
+            .with_gas_price(U256::from(10));
+        let report = simulator
+            .check_transaction_with_options(
+                call,
+                CheckOptions {
+                    mode: DiscoveryMode::Outflow,
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        assert!(report.succeeded());
+        assert_eq!(report.requirements.len(), 1);
+        assert_eq!(report.requirements[0].account, sender);
+        assert_eq!(
+            report.requirements[0].required,
+            AssetSpec::Native(U256::from(21_000 * 10) + value)
         );
         Ok(())
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_builder_env_options() -> Result<(), eyre::Error> {
+        let coinbase = Address::repeat_byte(0xcb);
+        let simulator = AssetSimulator::builder()
+            .with_chain_id(8453)
+            .with_spec_id(SpecId::CANCUN)
+            .with_block_number(30_000_000)
+            .with_block_timestamp(1_750_000_000)
+            .with_coinbase(coinbase)
+            .with_block_gas_limit(60_000_000)
+            .build()
+            .await?;
+
+        let executor = &simulator.executor;
+        assert_eq!(executor.spec_id(), SpecId::CANCUN);
+        let env = executor.env();
+        assert_eq!(env.cfg.chain_id, 8453);
+        assert_eq!(env.block.number, U256::from(30_000_000));
+        assert_eq!(env.block.timestamp, U256::from(1_750_000_000));
+        assert_eq!(env.block.coinbase, coinbase);
+        assert_eq!(env.block.gas_limit, U256::from(60_000_000));
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_basefee_applies_to_calls() -> Result<(), eyre::Error> {
+        let mut simulator = AssetSimulator::builder().with_basefee(7).build().await?;
+        let sender = Address::repeat_byte(0xa1);
+        let target = Address::repeat_byte(0xbf);
+
+        // Runtime: return BASEFEE
+        simulator.executor_mut().set_code(
+            target,
+            Bytecode::new_raw(Bytes::from_str("4860005260206000f3")?),
+        )?;
+        simulator
+            .executor_mut()
+            .set_balance(sender, U256::from(1_000_000_000))?;
+
+        let call = |gas_price: u64| {
+            Call::new(sender, target, Bytes::new(), U256::ZERO)
+                .with_gas_limit(100_000)
+                .with_gas_price(U256::from(gas_price))
+        };
+        let executor = &simulator.executor;
+        let result = executor.call_with_env(tx_env(executor, &call(7)))?;
+        assert_eq!(U256::from_be_slice(&result.result), U256::from(7));
+
+        // Below the basefee the tx is rejected outright
+        assert!(executor.call_with_env(tx_env(executor, &call(6))).is_err());
+
+        // Without a gas price of its own, a call still runs for free
+        let report = simulator
+            .check_transaction(Call::new(
+                Address::repeat_byte(0xa2),
+                target,
+                Bytes::new(),
+                U256::ZERO,
+            ))
+            .await?;
+        assert!(report.succeeded());
+        assert!(report.requirements.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_fork_with_block_timestamp() -> Result<(), eyre::Error> {
+        let simulator = AssetSimulator::builder()
+            .with_fork("https://mainnet.base.org", Some(30155463))
+            .with_block_timestamp(1)
+            .build()
+            .await?;
+
+        // Forked block, chosen time
+        let env = simulator.executor.env();
+        assert_eq!(env.cfg.chain_id, 8453);
+        assert_eq!(env.block.number, U256::from(30155463));
+        assert_eq!(env.block.timestamp, U256::from(1));
+        Ok(())
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn test_empty_transaction() -> Result<(), eyre::Error> {
         let mut simulator = AssetSimulator::builder()
@@ -363,7 +3472,7 @@ mod tests {
             .await?;
 
         let call = Call::default();
-        let result = simulator.check_transaction(call).await?;
+        let result = simulator.check_transaction(call).await?.requirements;
         assert!(result.is_empty());
         Ok(())
     }
@@ -394,7 +3503,7 @@ mod tests {
         );
 
         // Use no_fix version to just detect without auto-fixing
-        let result = simulator.check_transaction(call).await?;
+        let result = simulator.check_transaction(call).await?.requirements;
 
         // We expect to find a missing asset since our test address likely doesn't have 1000 USDC
         assert!(!result.is_empty());
@@ -474,7 +3583,7 @@ mod tests {
 
         // Run the check - uses auto-fix, so it will iterate, patch storage and
         // finally return every missing asset it encountered.
-        let missing = simulator.check_transaction(call).await?;
+        let missing = simulator.check_transaction(call).await?.requirements;
 
         assert_eq!(
             missing.len(),
@@ -493,6 +3602,12 @@ mod tests {
         assert_eq!(by_token.get(&usdc), Some(&amount_usdc));
         assert_eq!(by_token.get(&weth), Some(&amount_weth));
 
+        // Both transfers happen below the Multicall-3 frame
+        for m in &missing {
+            let frame = m.frame.expect("finding should reference its frame");
+            assert!(frame.depth > 0, "nested transfer should not be top-level");
+        }
+
         Ok(())
     }
 }
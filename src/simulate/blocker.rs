@@ -0,0 +1,287 @@
+//! Classification of a revert that discovery couldn't fund its way past.
+//!
+//! The revert data of every frame on the revert path is decoded, innermost
+//! first (well-known custom errors, then `Error(string)` patterns). Failing
+//! that, the tokens on the path are asked about well-known hooks: `paused()`
+//! and the USDC / USDT blocklists.
+
+use alloy_primitives::Address as AAddress;
+use alloy_sol_types::{Revert, SolError, sol};
+use forge::executors::Executor;
+use forge::revm::primitives::{Address, U256};
+use forge::traces::CallTraceNode;
+
+use crate::simulate::asset_simulator::tx_env;
+use crate::simulate::report::DiscoveryReport;
+use crate::simulate::types::{AuthorizationFailure, Call, PermitFailure, RequirementKind};
+use crate::simulate::utils::{candidate_frames, view_call};
+
+sol! {
+    // OpenZeppelin v5
+    error EnforcedPause();
+    error OwnableUnauthorizedAccount(address account);
+    error AccessControlUnauthorizedAccount(address account, bytes32 neededRole);
+    error ERC2612ExpiredSignature(uint256 deadline);
+    error ERC2612InvalidSigner(address signer, address owner);
+    error ECDSAInvalidSignature();
+    error ECDSAInvalidSignatureLength(uint256 length);
+    error ECDSAInvalidSignatureS(bytes32 s);
+    // Solady
+    error Unauthorized();
+    // Uniswap Universal Router / Permit2
+    error TransactionDeadlinePassed();
+    error V2TooLittleReceived();
+    error V2TooMuchRequested();
+    error V3TooLittleReceived();
+    error V3TooMuchRequested();
+    error SignatureExpired(uint256 signatureDeadline);
+    error InvalidSigner();
+    error InvalidSignature();
+    error InvalidNonce();
+    // Uniswap V2 style pools and others
+    error InsufficientLiquidity();
+    error InsufficientOutputAmount();
+
+    function paused() external view returns (bool);
+    // USDC (FiatToken)
+    function isBlacklisted(address account) external view returns (bool);
+    // USDT
+    function isBlackListed(address account) external view returns (bool);
+}
+
+/// Why a transaction still reverts. None of these can be fixed by dealing
+/// assets to the accounts of interest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlockerCategory {
+    /// A token or protocol on the path is paused.
+    Paused,
+    /// An account is on a token's blocklist (e.g. USDC, USDT).
+    Blacklisted,
+    /// A pool can't provide the requested amount.
+    InsufficientLiquidity,
+    /// A minimum-output / maximum-input check failed.
+    Slippage,
+    /// The caller lacks a role or isn't the owner.
+    AccessControl,
+    /// A signature (permit, authorization, order) was rejected.
+    BadSignature,
+    /// A deadline has passed.
+    ExpiredDeadline,
+    /// Nothing recognisable.
+    Unknown,
+}
+
+const CUSTOM_ERRORS: &[([u8; 4], BlockerCategory)] = &[
+    (EnforcedPause::SELECTOR, BlockerCategory::Paused),
+    (
+        OwnableUnauthorizedAccount::SELECTOR,
+        BlockerCategory::AccessControl,
+    ),
+    (
+        AccessControlUnauthorizedAccount::SELECTOR,
+        BlockerCategory::AccessControl,
+    ),
+    (Unauthorized::SELECTOR, BlockerCategory::AccessControl),
+    (
+        ERC2612ExpiredSignature::SELECTOR,
+        BlockerCategory::ExpiredDeadline,
+    ),
+    (
+        TransactionDeadlinePassed::SELECTOR,
+        BlockerCategory::ExpiredDeadline,
+    ),
+    (SignatureExpired::SELECTOR, BlockerCategory::ExpiredDeadline),
+    (
+        ERC2612InvalidSigner::SELECTOR,
+        BlockerCategory::BadSignature,
+    ),
+    (
+        ECDSAInvalidSignature::SELECTOR,
+        BlockerCategory::BadSignature,
+    ),
+    (
+        ECDSAInvalidSignatureLength::SELECTOR,
+        BlockerCategory::BadSignature,
+    ),
+    (
+        ECDSAInvalidSignatureS::SELECTOR,
+        BlockerCategory::BadSignature,
+    ),
+    (InvalidSigner::SELECTOR, BlockerCategory::BadSignature),
+    (InvalidSignature::SELECTOR, BlockerCategory::BadSignature),
+    (InvalidNonce::SELECTOR, BlockerCategory::BadSignature),
+    (V2TooLittleReceived::SELECTOR, BlockerCategory::Slippage),
+    (V2TooMuchRequested::SELECTOR, BlockerCategory::Slippage),
+    (V3TooLittleReceived::SELECTOR, BlockerCategory::Slippage),
+    (V3TooMuchRequested::SELECTOR, BlockerCategory::Slippage),
+    (
+        InsufficientOutputAmount::SELECTOR,
+        BlockerCategory::Slippage,
+    ),
+    (
+        InsufficientLiquidity::SELECTOR,
+        BlockerCategory::InsufficientLiquidity,
+    ),
+];
+
+// Lowercase fragments of `Error(string)` reasons, checked in this order
+const REASON_PATTERNS: &[(&str, BlockerCategory)] = &[
+    ("blacklist", BlockerCategory::Blacklisted),
+    ("blocklist", BlockerCategory::Blacklisted),
+    ("blocked", BlockerCategory::Blacklisted),
+    ("frozen", BlockerCategory::Blacklisted),
+    ("paused", BlockerCategory::Paused),
+    ("expired", BlockerCategory::ExpiredDeadline),
+    ("deadline", BlockerCategory::ExpiredDeadline),
+    ("too old", BlockerCategory::ExpiredDeadline),
+    ("signature", BlockerCategory::BadSignature),
+    ("invalid signer", BlockerCategory::BadSignature),
+    ("slippage", BlockerCategory::Slippage),
+    ("too little received", BlockerCategory::Slippage),
+    ("too much requested", BlockerCategory::Slippage),
+    ("insufficient_output_amount", BlockerCategory::Slippage),
+    ("excessive_input_amount", BlockerCategory::Slippage),
+    ("liquidity", BlockerCategory::InsufficientLiquidity),
+    ("ownable", BlockerCategory::AccessControl),
+    ("not the owner", BlockerCategory::AccessControl),
+    ("accesscontrol", BlockerCategory::AccessControl),
+    ("missing role", BlockerCategory::AccessControl),
+    ("unauthorized", BlockerCategory::AccessControl),
+    ("not authorized", BlockerCategory::AccessControl),
+    ("forbidden", BlockerCategory::AccessControl),
+];
+
+/// Classify why `call` still fails after `report`'s discovery, on the state
+/// the discovery left behind.
+pub(crate) fn classify(
+    executor: &Executor,
+    call: &Call,
+    report: &DiscoveryReport,
+) -> BlockerCategory {
+    if let Some(category) = from_findings(report) {
+        return category;
+    }
+
+    // Rejected before execution, or no longer failing at all
+    let Some(result) = executor
+        .call_with_env(tx_env(executor, call))
+        .ok()
+        .filter(|result| result.reverted)
+    else {
+        return BlockerCategory::Unknown;
+    };
+    let Some(traces) = result.traces else {
+        return decode(&result.result).unwrap_or(BlockerCategory::Unknown);
+    };
+
+    let mut frames = candidate_frames(&traces);
+    frames.reverse();
+    frames
+        .iter()
+        .find_map(|node| decode(&node.trace.output))
+        .or_else(|| frames.iter().find_map(|node| from_hooks(executor, node)))
+        .unwrap_or(BlockerCategory::Unknown)
+}
+
+// Signatures the checkers already diagnosed
+fn from_findings(report: &DiscoveryReport) -> Option<BlockerCategory> {
+    report
+        .requirements
+        .iter()
+        .find_map(|missing| match missing.kind {
+            RequirementKind::InvalidPermit {
+                reason: PermitFailure::ExpiredDeadline,
+                ..
+            }
+            | RequirementKind::InvalidAuthorization {
+                reason: AuthorizationFailure::Expired,
+            } => Some(BlockerCategory::ExpiredDeadline),
+            RequirementKind::InvalidAuthorization {
+                reason: AuthorizationFailure::CallerNotPayee,
+            } => Some(BlockerCategory::AccessControl),
+            RequirementKind::InvalidPermit { .. }
+            | RequirementKind::InvalidAuthorization { .. } => Some(BlockerCategory::BadSignature),
+            _ => None,
+        })
+}
+
+fn decode(revert_data: &[u8]) -> Option<BlockerCategory> {
+    let selector: [u8; 4] = revert_data.get(..4)?.try_into().ok()?;
+    if let Some(&(_, category)) = CUSTOM_ERRORS.iter().find(|(known, _)| *known == selector) {
+        return Some(category);
+    }
+
+    let reason = Revert::abi_decode(revert_data).ok()?.reason.to_lowercase();
+    REASON_PATTERNS
+        .iter()
+        .find(|(pattern, _)| reason.contains(pattern))
+        .map(|&(_, category)| category)
+}
+
+// A paused token, or one blocking the caller or an address passed to it
+fn from_hooks(executor: &Executor, node: &CallTraceNode) -> Option<BlockerCategory> {
+    let token = node.trace.address;
+    if view_call(executor, token, &pausedCall {}) == Some(true) {
+        return Some(BlockerCategory::Paused);
+    }
+
+    let blocked = |account: Address| {
+        let account = AAddress::from_slice(account.as_slice());
+        view_call(executor, token, &isBlacklistedCall { account }) == Some(true)
+            || view_call(executor, token, &isBlackListedCall { account }) == Some(true)
+    };
+    std::iter::once(node.trace.caller)
+        .chain(address_arguments(&node.trace.data))
+        .any(blocked)
+        .then_some(BlockerCategory::Blacklisted)
+}
+
+// Words of ABI-encoded arguments that look like addresses
+fn address_arguments(calldata: &[u8]) -> impl Iterator<Item = Address> + '_ {
+    calldata
+        .get(4..)
+        .unwrap_or_default()
+        .chunks_exact(32)
+        .filter(|word| word[..12].iter().all(|&byte| byte == 0))
+        .map(|word| Address::from_slice(&word[12..]))
+        .filter(|address| U256::from_be_slice(address.as_slice()) > U256::from(u32::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_string(reason: &str) -> Vec<u8> {
+        Revert::from(reason.to_string()).abi_encode()
+    }
+
+    #[test]
+    fn decodes_well_known_reverts() {
+        assert_eq!(
+            decode(&EnforcedPause {}.abi_encode()),
+            Some(BlockerCategory::Paused)
+        );
+        assert_eq!(
+            decode(&error_string("Blacklistable: account is blacklisted")),
+            Some(BlockerCategory::Blacklisted)
+        );
+        assert_eq!(
+            decode(&error_string("ERC20Permit: expired deadline")),
+            Some(BlockerCategory::ExpiredDeadline)
+        );
+        assert_eq!(
+            decode(&error_string("UniswapV2Router: INSUFFICIENT_OUTPUT_AMOUNT")),
+            Some(BlockerCategory::Slippage)
+        );
+        assert_eq!(
+            decode(&error_string("UniswapV2: INSUFFICIENT_LIQUIDITY")),
+            Some(BlockerCategory::InsufficientLiquidity)
+        );
+        assert_eq!(
+            decode(&error_string("Ownable: caller is not the owner")),
+            Some(BlockerCategory::AccessControl)
+        );
+        assert_eq!(decode(&error_string("something else")), None);
+        assert_eq!(decode(&[]), None);
+    }
+}
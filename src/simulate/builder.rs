@@ -1,19 +1,36 @@
 use crate::simulate::{
-    checkers::{AssetChecker, ERC20Checker},
-    types::ForkInfo,
+    checkers::{
+        AllowanceChecker, AssetChecker, ERC20Checker, ERC721Checker, ERC1155Checker, NativeChecker,
+    },
+    overrides::{StateOverride, apply_state_override},
+    types::{AssetGrant, ForkInfo},
 };
 use forge::{
-    backend::Backend, executors::ExecutorBuilder, revm::primitives::Env, traces::TraceMode,
+    backend::Backend,
+    executors::ExecutorBuilder,
+    revm::primitives::{Address, BlockEnv, Env, SpecId, U256},
+    traces::TraceMode,
 };
 use foundry_config::Config;
 use foundry_evm_core::opts::EvmOpts;
+use std::collections::HashSet;
 
 #[derive(Default)]
 pub struct AssetSimulatorBuilder {
-    env: Env,
+    env: Option<Env>,
+    chain_id: Option<u64>,
+    spec_id: Option<SpecId>,
+    block_number: Option<u64>,
+    block_timestamp: Option<u64>,
+    basefee: Option<u64>,
+    coinbase: Option<Address>,
+    block_gas_limit: Option<u64>,
     fork_info: Option<ForkInfo>,
     backend: Option<Backend>,
     checkers: Vec<Box<dyn AssetChecker>>,
+    accounts_of_interest: HashSet<Address>,
+    grants: Vec<AssetGrant>,
+    state_override: StateOverride,
 }
 
 impl AssetSimulatorBuilder {
@@ -25,8 +42,52 @@ impl AssetSimulatorBuilder {
         self
     }
 
+    /// Execute in `env` instead of the fork's block (or revm's defaults).
+    /// The typed options below are applied on top of it.
     pub fn with_env(mut self, env: Env) -> Self {
-        self.env = env;
+        self.env = Some(env);
+        self
+    }
+
+    pub fn with_chain_id(mut self, chain_id: u64) -> Self {
+        self.chain_id = Some(chain_id);
+        self
+    }
+
+    /// Hardfork rules to execute with.
+    pub fn with_spec_id(mut self, spec_id: SpecId) -> Self {
+        self.spec_id = Some(spec_id);
+        self
+    }
+
+    /// Block number seen by the executed calls. Doesn't change the block
+    /// state is forked from, see `with_fork`.
+    pub fn with_block_number(mut self, block_number: u64) -> Self {
+        self.block_number = Some(block_number);
+        self
+    }
+
+    /// Block timestamp seen by the executed calls, e.g. to fork at block N
+    /// but check a deadline-sensitive tx at time T.
+    pub fn with_block_timestamp(mut self, timestamp: u64) -> Self {
+        self.block_timestamp = Some(timestamp);
+        self
+    }
+
+    /// Calls that set a gas price then need at least `basefee`; calls
+    /// without one still run at a zero gas price (and basefee).
+    pub fn with_basefee(mut self, basefee: u64) -> Self {
+        self.basefee = Some(basefee);
+        self
+    }
+
+    pub fn with_coinbase(mut self, coinbase: Address) -> Self {
+        self.coinbase = Some(coinbase);
+        self
+    }
+
+    pub fn with_block_gas_limit(mut self, gas_limit: u64) -> Self {
+        self.block_gas_limit = Some(gas_limit);
         self
     }
 
@@ -35,10 +96,56 @@ impl AssetSimulatorBuilder {
         self
     }
 
+    /// Only report and deal shortfalls of `account` (may be called repeatedly).
+    /// Shortfalls of any other account come back as external blockers.
+    pub fn with_account_of_interest(mut self, account: Address) -> Self {
+        self.accounts_of_interest.insert(account);
+        self
+    }
+
+    pub fn with_accounts_of_interest(
+        mut self,
+        accounts: impl IntoIterator<Item = Address>,
+    ) -> Self {
+        self.accounts_of_interest.extend(accounts);
+        self
+    }
+
+    /// Apply `grants` once the simulator is built (may be called repeatedly).
+    /// Each grant is dealt by a checker of its asset type, so add those too.
+    pub fn with_grants(mut self, grants: impl IntoIterator<Item = AssetGrant>) -> Self {
+        self.grants.extend(grants);
+        self
+    }
+
+    /// Apply a geth-style state override once the simulator is built, before
+    /// any grants. Later calls override earlier ones per address.
+    pub fn with_state_override(mut self, state_override: StateOverride) -> Self {
+        self.state_override.extend(state_override);
+        self
+    }
+
     pub fn with_erc20_checker(self) -> Self {
         self.with_checker(ERC20Checker::new())
     }
 
+    /// Report (and, when auto-fixing, deal) missing ERC20 allowances.
+    pub fn with_allowance_checker(self) -> Self {
+        self.with_checker(AllowanceChecker::new())
+    }
+
+    pub fn with_erc721_checker(self) -> Self {
+        self.with_checker(ERC721Checker::new())
+    }
+
+    pub fn with_erc1155_checker(self) -> Self {
+        self.with_checker(ERC1155Checker::new())
+    }
+
+    pub fn with_native_checker(self) -> Self {
+        self.with_checker(NativeChecker::new())
+    }
+
     pub fn with_checker<T: AssetChecker + 'static>(mut self, checker: T) -> Self {
         self.checkers.push(Box::new(checker));
         self
@@ -49,6 +156,7 @@ impl AssetSimulatorBuilder {
         self,
     ) -> Result<crate::simulate::asset_simulator::AssetSimulator, eyre::Error> {
         // ── select / build backend ────────────────────────────────────────────────
+        let mut fork_env = None;
         let backend = if let Some(backend) = self.backend {
             backend
         } else {
@@ -64,19 +172,63 @@ impl AssetSimulatorBuilder {
 
             let cfg = Config::default();
             let backend_env = opts.evm_env().await?;
+            if self.fork_info.is_some() {
+                fork_env = Some(backend_env.clone());
+            }
             Backend::spawn(opts.get_fork(&cfg, backend_env))?
         };
 
+        // ── env ──────────────────────────────────────────────────────────────────
+        let mut env = match (self.env, fork_env) {
+            (Some(env), _) => env,
+            // Execute in the forked block. Its basefee is left out, as calls
+            // default to a zero gas price.
+            (None, Some(fork_env)) => {
+                let mut env = Env::default();
+                env.cfg.chain_id = fork_env.cfg.chain_id;
+                env.block = BlockEnv {
+                    basefee: U256::ZERO,
+                    ..fork_env.block
+                };
+                env
+            }
+            (None, None) => Env::default(),
+        };
+        if let Some(chain_id) = self.chain_id {
+            env.cfg.chain_id = chain_id;
+        }
+        if let Some(number) = self.block_number {
+            env.block.number = U256::from(number);
+        }
+        if let Some(timestamp) = self.block_timestamp {
+            env.block.timestamp = U256::from(timestamp);
+        }
+        if let Some(basefee) = self.basefee {
+            env.block.basefee = U256::from(basefee);
+        }
+        if let Some(coinbase) = self.coinbase {
+            env.block.coinbase = coinbase;
+        }
+        if let Some(gas_limit) = self.block_gas_limit {
+            env.block.gas_limit = U256::from(gas_limit);
+        }
+
         // ── executor ─────────────────────────────────────────────────────────────
-        let executor = ExecutorBuilder::new()
-            .inspectors(|stack| stack.trace_mode(TraceMode::Debug))
-            .build(self.env.clone(), backend);
-
-        Ok(
-            crate::simulate::asset_simulator::AssetSimulator::new_from_parts(
-                executor,
-                self.checkers,
-            ),
-        )
+        let mut executor_builder =
+            ExecutorBuilder::new().inspectors(|stack| stack.trace_mode(TraceMode::Debug));
+        if let Some(spec_id) = self.spec_id {
+            executor_builder = executor_builder.spec_id(spec_id);
+        }
+        let mut executor = executor_builder.build(env, backend);
+
+        apply_state_override(&self.state_override, &mut executor)?;
+
+        let mut simulator = crate::simulate::asset_simulator::AssetSimulator::new_from_parts(
+            executor,
+            self.checkers,
+            self.accounts_of_interest,
+        );
+        simulator.apply_grants(self.grants)?;
+        Ok(simulator)
     }
 }
@@ -0,0 +1,156 @@
+use alloy_primitives::Address as AAddress;
+use alloy_sol_types::SolCall;
+use forge::executors::Executor;
+use forge::revm::primitives::{Address, U256};
+use forge::traces::CallTrace;
+use log::{debug, info};
+use std::collections::HashMap;
+
+use crate::simulate::checkers::erc20::{allowanceCall, transferFromCall};
+use crate::simulate::checkers::erc721::ERC721_INTERFACE_ID;
+use crate::simulate::checkers::storage::{accessed_slots, probe_slots};
+use crate::simulate::checkers::traits::{AssetChecker, PotentialMissingAsset};
+use crate::simulate::types::{
+    AssetContext, AssetSpec, AssetType, MissingAssetInfo, RequirementKind,
+};
+use crate::simulate::utils::{supports_interface, view_call};
+
+// ERC20 allowance checker: reports `transferFrom` calls whose spender isn't
+// approved for the amount, as `RequirementKind::Allowance` on the owner.
+#[derive(Default)]
+pub struct AllowanceChecker;
+
+impl AllowanceChecker {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl AssetChecker for AllowanceChecker {
+    fn identify_asset(&self, trace: &CallTrace) -> Option<PotentialMissingAsset> {
+        let call = transferFromCall::abi_decode(trace.data.as_ref()).ok()?;
+        let owner = Address::from_slice(call.from.as_slice());
+
+        // `transferFrom(self, ...)` spends an allowance on OpenZeppelin-style
+        // tokens, but not on ones that exempt the owner (WETH & co). Only a
+        // call that went through tells them apart.
+        if owner == trace.caller && trace.success {
+            return None;
+        }
+
+        Some(PotentialMissingAsset {
+            asset_type: AssetType::ERC20,
+            token_address: trace.address,
+            account: owner,
+            required_amount: call.amount,
+            spender: trace.caller,
+            token_ids: Vec::new(),
+            token_amounts: HashMap::new(),
+            calldata: trace.data.clone(),
+        })
+    }
+
+    fn check_balance(
+        &self,
+        asset: PotentialMissingAsset,
+        executor: &mut Executor,
+    ) -> Result<MissingAssetInfo, eyre::Error> {
+        let required = AssetSpec::ERC20 {
+            token: asset.token_address,
+            amount: asset.required_amount,
+        };
+        let kind = RequirementKind::Allowance {
+            spender: asset.spender,
+        };
+
+        // `transferFrom` shares its selector with ERC-721
+        if supports_interface(executor, asset.token_address, ERC721_INTERFACE_ID) {
+            return Ok(
+                MissingAssetInfo::new(asset.account, required, U256::ZERO, U256::ZERO)
+                    .with_kind(kind),
+            );
+        }
+
+        let current = allowance_of(executor, asset.token_address, asset.account, asset.spender);
+        let missing_amount = asset.required_amount.saturating_sub(current);
+
+        Ok(MissingAssetInfo::new(asset.account, required, current, missing_amount).with_kind(kind))
+    }
+
+    fn deal(
+        &self,
+        recipient: Address,
+        asset_spec: AssetSpec,
+        executor: &mut Executor,
+        context: &AssetContext,
+    ) -> Result<(), eyre::Error> {
+        if let AssetSpec::ERC20 { token, amount } = asset_spec {
+            let spender = context.potential_asset.spender;
+
+            let allowance_call = allowanceCall {
+                owner: AAddress::from_slice(recipient.as_slice()),
+                spender: AAddress::from_slice(spender.as_slice()),
+            };
+            let slots = accessed_slots(executor, token, allowance_call.abi_encode().into())?;
+            debug!(
+                "allowance({:?}, {:?}) reads slots {:?}",
+                recipient, spender, slots
+            );
+
+            let before = allowance_of(executor, token, recipient, spender);
+            if before >= amount {
+                return Ok(()); // Never lower an allowance
+            }
+            let found = probe_slots(
+                executor,
+                token,
+                &slots,
+                |_| Some(amount),
+                |executor| {
+                    let after = allowance_of(executor, token, recipient, spender);
+                    after != before && after >= amount
+                },
+            )?;
+
+            match found {
+                Some(slot) => {
+                    info!(
+                        "Allowance of {:?} for {:?} on {:?} lives in slot {:?}",
+                        recipient, spender, token, slot
+                    );
+                    Ok(())
+                }
+                None => Err(eyre::eyre!(
+                    "no storage slot of {:?} controls allowance({:?}, {:?})",
+                    token,
+                    recipient,
+                    spender
+                )),
+            }
+        } else {
+            Err(eyre::eyre!(
+                "AllowanceChecker can only deal ERC20 allowances"
+            ))
+        }
+    }
+
+    fn asset_type(&self) -> AssetType {
+        AssetType::ERC20
+    }
+
+    fn deals_holdings(&self) -> bool {
+        false
+    }
+}
+
+fn allowance_of(executor: &Executor, token: Address, owner: Address, spender: Address) -> U256 {
+    view_call(
+        executor,
+        token,
+        &allowanceCall {
+            owner: AAddress::from_slice(owner.as_slice()),
+            spender: AAddress::from_slice(spender.as_slice()),
+        },
+    )
+    .unwrap_or(U256::ZERO)
+}
@@ -0,0 +1,326 @@
+use alloy_primitives::Address as AAddress;
+use alloy_sol_types::{SolCall, sol};
+use forge::executors::Executor;
+use forge::revm::primitives::{Address, U256};
+use forge::traces::CallTrace;
+use log::info;
+use std::collections::HashMap;
+
+use crate::simulate::checkers::storage::{accessed_slots, probe_slots};
+use crate::simulate::checkers::traits::{AssetChecker, PotentialMissingAsset};
+use crate::simulate::types::{
+    AssetContext, AssetSpec, AssetType, MissingAssetInfo, RequirementKind,
+};
+use crate::simulate::utils::{supports_interface, transact_as, view_call};
+
+/// ERC-165 interface id of ERC-1155.
+pub const ERC1155_INTERFACE_ID: [u8; 4] = [0xd9, 0xb6, 0x7a, 0x26];
+
+sol! {
+    interface IERC1155 {
+        function safeTransferFrom(address from, address to, uint256 id, uint256 value, bytes data) external;
+        function safeBatchTransferFrom(address from, address to, uint256[] ids, uint256[] values, bytes data) external;
+        function balanceOf(address account, uint256 id) external view returns (uint256);
+        function balanceOfBatch(address[] accounts, uint256[] ids) external view returns (uint256[]);
+        function isApprovedForAll(address account, address operator) external view returns (bool);
+        function setApprovalForAll(address operator, bool approved) external;
+    }
+}
+
+// ERC1155 checker implementation
+#[derive(Default)]
+pub struct ERC1155Checker;
+
+impl ERC1155Checker {
+    pub fn new() -> Self {
+        Self
+    }
+
+    // Decode a transfer into (from, id → amount); repeated ids in a batch add up
+    fn decode(data: &[u8]) -> Option<(Address, HashMap<U256, U256>)> {
+        if let Ok(call) = IERC1155::safeTransferFromCall::abi_decode(data) {
+            let from = Address::from_slice(call.from.as_slice());
+            return Some((from, HashMap::from([(call.id, call.value)])));
+        }
+        if let Ok(call) = IERC1155::safeBatchTransferFromCall::abi_decode(data) {
+            let from = Address::from_slice(call.from.as_slice());
+            let mut amounts = HashMap::new();
+            for (id, value) in call.ids.into_iter().zip(call.values) {
+                *amounts.entry(id).or_insert(U256::ZERO) += value;
+            }
+            return Some((from, amounts));
+        }
+        None
+    }
+}
+
+impl AssetChecker for ERC1155Checker {
+    fn identify_asset(&self, trace: &CallTrace) -> Option<PotentialMissingAsset> {
+        let (account, token_amounts) = Self::decode(trace.data.as_ref())?;
+
+        Some(PotentialMissingAsset {
+            asset_type: AssetType::ERC1155,
+            token_address: trace.address,
+            account,
+            required_amount: token_amounts.values().copied().sum(),
+            spender: trace.caller,
+            token_ids: Vec::new(),
+            token_amounts,
+            calldata: trace.data.clone(),
+        })
+    }
+
+    /// The first shortfall found, see `check_balances`.
+    fn check_balance(
+        &self,
+        asset: PotentialMissingAsset,
+        executor: &mut Executor,
+    ) -> Result<MissingAssetInfo, eyre::Error> {
+        let mut findings = self.check_balances(asset, executor)?;
+        Ok(findings.remove(0))
+    }
+
+    /// One finding per id the account is short on, each with its own balance
+    /// and missing amount; if none is short, a single finding for the whole
+    /// transfer (which may still miss an operator approval).
+    fn check_balances(
+        &self,
+        asset: PotentialMissingAsset,
+        executor: &mut Executor,
+    ) -> Result<Vec<MissingAssetInfo>, eyre::Error> {
+        let token = asset.token_address;
+
+        if !supports_interface(executor, token, ERC1155_INTERFACE_ID) {
+            return Ok(vec![MissingAssetInfo::new(
+                asset.account,
+                AssetSpec::ERC1155 {
+                    token,
+                    token_amounts: asset.token_amounts,
+                },
+                U256::ZERO,
+                U256::ZERO,
+            )]);
+        }
+
+        let mut ids: Vec<U256> = asset.token_amounts.keys().copied().collect();
+        ids.sort();
+        let balances = balances_of(executor, token, asset.account, &ids);
+
+        let short: Vec<MissingAssetInfo> = ids
+            .iter()
+            .zip(balances)
+            .filter_map(|(id, balance)| {
+                let required = asset.token_amounts[id];
+                (balance < required).then(|| {
+                    MissingAssetInfo::new(
+                        asset.account,
+                        AssetSpec::ERC1155 {
+                            token,
+                            token_amounts: HashMap::from([(*id, required)]),
+                        },
+                        balance,
+                        required - balance,
+                    )
+                })
+            })
+            .collect();
+        if !short.is_empty() {
+            return Ok(short);
+        }
+
+        // Balances are fine; an operator still needs the holder's blessing
+        let required = AssetSpec::ERC1155 {
+            token,
+            token_amounts: asset.token_amounts,
+        };
+        if asset.spender != asset.account
+            && !is_approved_for_all(executor, token, asset.account, asset.spender)
+        {
+            return Ok(vec![
+                MissingAssetInfo::new(asset.account, required, U256::ZERO, U256::from(1))
+                    .with_kind(RequirementKind::Approval {
+                        operator: asset.spender,
+                    }),
+            ]);
+        }
+
+        Ok(vec![MissingAssetInfo::new(
+            asset.account,
+            required,
+            asset.required_amount,
+            U256::ZERO,
+        )])
+    }
+
+    fn deal(
+        &self,
+        recipient: Address,
+        asset_spec: AssetSpec,
+        executor: &mut Executor,
+        context: &AssetContext,
+    ) -> Result<(), eyre::Error> {
+        if let AssetSpec::ERC1155 {
+            token,
+            token_amounts,
+        } = asset_spec
+        {
+            for (id, amount) in token_amounts {
+                set_balance(executor, token, recipient, id, amount)?;
+            }
+
+            let operator = context.potential_asset.spender;
+            if operator != recipient && !is_approved_for_all(executor, token, recipient, operator) {
+                let approval = IERC1155::setApprovalForAllCall {
+                    operator: AAddress::from_slice(operator.as_slice()),
+                    approved: true,
+                };
+                let result = transact_as(executor, recipient, token, approval.abi_encode())?;
+                if result.reverted {
+                    return Err(eyre::eyre!(
+                        "setApprovalForAll({:?}) reverted on {:?}",
+                        operator,
+                        token
+                    ));
+                }
+            }
+
+            Ok(())
+        } else {
+            Err(eyre::eyre!("ERC1155Checker can only deal ERC1155 assets"))
+        }
+    }
+
+    fn asset_type(&self) -> AssetType {
+        AssetType::ERC1155
+    }
+}
+
+// ------------------------------------------------------------------------
+//  Views
+// ------------------------------------------------------------------------
+
+fn balance_of(executor: &Executor, token: Address, account: Address, id: U256) -> U256 {
+    view_call(
+        executor,
+        token,
+        &IERC1155::balanceOfCall {
+            account: AAddress::from_slice(account.as_slice()),
+            id,
+        },
+    )
+    .unwrap_or(U256::ZERO)
+}
+
+/// One `balanceOfBatch` round-trip, falling back to per-id `balanceOf` for
+/// tokens with a broken batch view.
+fn balances_of(executor: &Executor, token: Address, account: Address, ids: &[U256]) -> Vec<U256> {
+    let batch = IERC1155::balanceOfBatchCall {
+        accounts: vec![AAddress::from_slice(account.as_slice()); ids.len()],
+        ids: ids.to_vec(),
+    };
+    match view_call(executor, token, &batch) {
+        Some(balances) if balances.len() == ids.len() => balances,
+        _ => ids
+            .iter()
+            .map(|&id| balance_of(executor, token, account, id))
+            .collect(),
+    }
+}
+
+fn is_approved_for_all(
+    executor: &Executor,
+    token: Address,
+    account: Address,
+    operator: Address,
+) -> bool {
+    view_call(
+        executor,
+        token,
+        &IERC1155::isApprovedForAllCall {
+            account: AAddress::from_slice(account.as_slice()),
+            operator: AAddress::from_slice(operator.as_slice()),
+        },
+    )
+    .unwrap_or(false)
+}
+
+// ------------------------------------------------------------------------
+//  Dealing
+// ------------------------------------------------------------------------
+
+/// Patch the `id => account => balance` mapping entry so that
+/// `balanceOf(account, id) == amount`.
+fn set_balance(
+    executor: &mut Executor,
+    token: Address,
+    account: Address,
+    id: U256,
+    amount: U256,
+) -> Result<(), eyre::Error> {
+    let current = balance_of(executor, token, account, id);
+    if current >= amount {
+        return Ok(());
+    }
+
+    let balance_call = IERC1155::balanceOfCall {
+        account: AAddress::from_slice(account.as_slice()),
+        id,
+    };
+    let slots = accessed_slots(executor, token, balance_call.abi_encode().into())?;
+
+    let found = probe_slots(
+        executor,
+        token,
+        &slots,
+        |value| (value == current).then_some(amount),
+        |executor| balance_of(executor, token, account, id) == amount,
+    )?;
+    if found.is_none() {
+        return Err(eyre::eyre!(
+            "no balance slot found for id {} of {:?} on {:?}",
+            id,
+            account,
+            token
+        ));
+    }
+
+    info!(
+        "Dealt ERC1155 {:?} #{}: {:?} now holds {}",
+        token, id, account, amount
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use forge::revm::primitives::Bytes;
+
+    #[test]
+    fn batch_transfer_sums_repeated_ids() {
+        let from = Address::repeat_byte(1);
+        let data = IERC1155::safeBatchTransferFromCall {
+            from: AAddress::from_slice(from.as_slice()),
+            to: AAddress::repeat_byte(2),
+            ids: vec![U256::from(1), U256::from(2), U256::from(1)],
+            values: vec![U256::from(5), U256::from(7), U256::from(3)],
+            data: Default::default(),
+        }
+        .abi_encode();
+
+        let trace = CallTrace {
+            data: Bytes::from(data),
+            caller: Address::repeat_byte(3),
+            address: Address::repeat_byte(9),
+            ..Default::default()
+        };
+
+        let asset = ERC1155Checker::new()
+            .identify_asset(&trace)
+            .expect("should decode safeBatchTransferFrom");
+        assert_eq!(asset.account, from);
+        assert_eq!(asset.token_amounts[&U256::from(1)], U256::from(8));
+        assert_eq!(asset.token_amounts[&U256::from(2)], U256::from(7));
+        assert_eq!(asset.required_amount, U256::from(15));
+    }
+}
@@ -1,19 +1,72 @@
-use alloy_primitives::Address as AAddress;
-use alloy_sol_types::{SolCall, sol};
+use alloy_primitives::{Address as AAddress, B256, keccak256};
+use alloy_sol_types::{SolCall, SolStruct, sol};
 use forge::executors::Executor;
-use forge::revm::primitives::{Address, U256};
+use forge::revm::DatabaseRef;
+use forge::revm::primitives::{Address, KECCAK_EMPTY, U256};
 use forge::traces::CallTrace;
+use log::{debug, info, warn};
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
 
+use crate::simulate::checkers::erc721::ERC721_INTERFACE_ID;
+use crate::simulate::checkers::slot_cache::{BalanceSlot, SlotCache, SlotCacheKey};
+use crate::simulate::checkers::storage::{probe_slots, record_reads};
 use crate::simulate::checkers::traits::{AssetChecker, PotentialMissingAsset};
-use crate::simulate::types::{AssetContext, AssetSpec, AssetType, MissingAssetInfo};
+use crate::simulate::error::AssetSimulatorError;
+use crate::simulate::types::{
+    AssetContext, AssetSpec, AssetType, AuthorizationFailure, MissingAssetInfo, PermitFailure,
+    RequirementKind,
+};
+use crate::simulate::utils::{ecrecover, supports_interface, view_call};
 
 // Define ERC20 function signatures
 sol! {
     function transfer(address to, uint256 amount) public returns (bool);
     function transferFrom(address from, address to, uint256 amount) public returns (bool);
     function balanceOf(address account) external view returns (uint256);
+    function allowance(address owner, address spender) external view returns (uint256);
+
+    // ERC-2612
+    function permit(address owner, address spender, uint256 value, uint256 deadline, uint8 v, bytes32 r, bytes32 s) external;
+    function nonces(address owner) external view returns (uint256);
+    function DOMAIN_SEPARATOR() external view returns (bytes32);
+
+    struct Permit {
+        address owner;
+        address spender;
+        uint256 value;
+        uint256 nonce;
+        uint256 deadline;
+    }
+
+    // EIP-3009
+    function transferWithAuthorization(address from, address to, uint256 value, uint256 validAfter, uint256 validBefore, bytes32 nonce, uint8 v, bytes32 r, bytes32 s) external;
+    function receiveWithAuthorization(address from, address to, uint256 value, uint256 validAfter, uint256 validBefore, bytes32 nonce, uint8 v, bytes32 r, bytes32 s) external;
+    function authorizationState(address authorizer, bytes32 nonce) external view returns (bool);
+
+    struct TransferWithAuthorization {
+        address from;
+        address to;
+        uint256 value;
+        uint256 validAfter;
+        uint256 validBefore;
+        bytes32 nonce;
+    }
+
+    struct ReceiveWithAuthorization {
+        address from;
+        address to;
+        uint256 value;
+        uint256 validAfter;
+        uint256 validBefore;
+        bytes32 nonce;
+    }
 }
 
+// Nonces on either side of the current one tried when a permit signature
+// doesn't recover to its owner
+const PERMIT_NONCE_WINDOW: u64 = 8;
+
 // Define a trait for ERC20 transfer operations (now object-safe)
 pub trait ERC20TransferCheck {
     fn get_account(&self, trace: &CallTrace) -> Address;
@@ -33,6 +86,18 @@ impl transferFromCall {
     }
 }
 
+impl transferWithAuthorizationCall {
+    fn try_decode(data: &[u8]) -> Option<Self> {
+        Self::abi_decode(data).ok()
+    }
+}
+
+impl receiveWithAuthorizationCall {
+    fn try_decode(data: &[u8]) -> Option<Self> {
+        Self::abi_decode(data).ok()
+    }
+}
+
 // Implementation for transfer
 impl ERC20TransferCheck for transferCall {
     fn get_account(&self, trace: &CallTrace) -> Address {
@@ -55,10 +120,33 @@ impl ERC20TransferCheck for transferFromCall {
     }
 }
 
+// Implementation for EIP-3009: the signer pays, whoever submits it
+impl ERC20TransferCheck for transferWithAuthorizationCall {
+    fn get_account(&self, _trace: &CallTrace) -> Address {
+        Address::from_slice(self.from.as_slice())
+    }
+
+    fn get_amount(&self) -> U256 {
+        self.value
+    }
+}
+
+impl ERC20TransferCheck for receiveWithAuthorizationCall {
+    fn get_account(&self, _trace: &CallTrace) -> Address {
+        Address::from_slice(self.from.as_slice())
+    }
+
+    fn get_amount(&self) -> U256 {
+        self.value
+    }
+}
+
 // ERC20 checker implementation
 pub struct ERC20Checker {
     // Store a list of transfer checkers
     transfer_checkers: Vec<fn(&[u8]) -> Option<Box<dyn ERC20TransferCheck>>>,
+    // Balance mapping slots learned (or bundled) so far
+    slot_cache: Mutex<SlotCache>,
 }
 
 impl ERC20Checker {
@@ -74,8 +162,94 @@ impl ERC20Checker {
                     transferFromCall::try_decode(data)
                         .map(|d| Box::new(d) as Box<dyn ERC20TransferCheck>)
                 },
+                |data| {
+                    transferWithAuthorizationCall::try_decode(data)
+                        .map(|d| Box::new(d) as Box<dyn ERC20TransferCheck>)
+                },
+                |data| {
+                    receiveWithAuthorizationCall::try_decode(data)
+                        .map(|d| Box::new(d) as Box<dyn ERC20TransferCheck>)
+                },
                 // Add more transfer types here as needed
             ],
+            slot_cache: Mutex::new(SlotCache::new()),
+        }
+    }
+
+    /// Use `cache` (e.g. a file-backed [`SlotCache::with_file`]) for balance slots.
+    pub fn with_slot_cache(mut self, cache: SlotCache) -> Self {
+        self.slot_cache = Mutex::new(cache);
+        self
+    }
+
+    fn slot_cache(&self) -> MutexGuard<'_, SlotCache> {
+        self.slot_cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // Cache key of the code reading the balance; `None` for code-less readers
+    fn cache_key(
+        executor: &Executor,
+        reader: Address,
+    ) -> Result<Option<SlotCacheKey>, eyre::Error> {
+        let code_hash = executor
+            .backend()
+            .basic_ref(reader)?
+            .map(|info| info.code_hash)
+            .filter(|hash| *hash != KECCAK_EMPTY);
+
+        Ok(code_hash.map(|code_hash| SlotCacheKey {
+            chain_id: executor.env().cfg.chain_id,
+            code_hash,
+        }))
+    }
+
+    // A rejected permit is reported (never dealt) with the allowance it
+    // would have granted as missing. Permits failing for reasons we can't
+    // classify are left to the frames around them.
+    fn check_permit(
+        asset: &PotentialMissingAsset,
+        permit: &permitCall,
+        executor: &Executor,
+    ) -> MissingAssetInfo {
+        let required = AssetSpec::ERC20 {
+            token: asset.token_address,
+            amount: asset.required_amount,
+        };
+        let current_allowance = view_call(
+            executor,
+            asset.token_address,
+            &allowanceCall {
+                owner: permit.owner,
+                spender: permit.spender,
+            },
+        )
+        .unwrap_or(U256::ZERO);
+
+        match diagnose_permit(executor, asset.token_address, permit) {
+            Some(reason) => {
+                debug!("permit of {:?} rejected: {:?}", asset.account, reason);
+                MissingAssetInfo::new(
+                    asset.account,
+                    required,
+                    current_allowance,
+                    asset.required_amount,
+                )
+                .with_kind(RequirementKind::InvalidPermit {
+                    spender: asset.spender,
+                    reason,
+                })
+            }
+            None => MissingAssetInfo::new(asset.account, required, current_allowance, U256::ZERO),
+        }
+    }
+
+    fn remember(&self, key: Option<SlotCacheKey>, slot: BalanceSlot) {
+        if let Some(key) = key {
+            if let Err(e) = self.slot_cache().insert(key, slot) {
+                warn!("Could not persist balance slot cache: {}", e);
+            }
         }
     }
 }
@@ -84,6 +258,25 @@ impl AssetChecker for ERC20Checker {
     fn identify_asset(&self, trace: &CallTrace) -> Option<PotentialMissingAsset> {
         let data = trace.data.as_ref();
 
+        // A reverted permit is diagnosed on its own. A successful one needs
+        // nothing: the downstream transferFrom is attributed to its `from`,
+        // i.e. the permit's owner.
+        if let Ok(permit) = permitCall::abi_decode(data) {
+            if trace.success {
+                return None;
+            }
+            return Some(PotentialMissingAsset {
+                asset_type: AssetType::ERC20,
+                token_address: trace.address,
+                account: Address::from_slice(permit.owner.as_slice()),
+                required_amount: permit.value,
+                spender: Address::from_slice(permit.spender.as_slice()),
+                token_ids: Vec::new(),
+                token_amounts: HashMap::new(),
+                calldata: trace.data.clone(),
+            });
+        }
+
         // Try each transfer checker until one succeeds
         for try_decode in &self.transfer_checkers {
             if let Some(decoded) = try_decode(data) {
@@ -92,6 +285,10 @@ impl AssetChecker for ERC20Checker {
                     token_address: trace.address,
                     account: decoded.get_account(trace),
                     required_amount: decoded.get_amount(),
+                    spender: trace.caller,
+                    token_ids: Vec::new(),
+                    token_amounts: HashMap::new(),
+                    calldata: trace.data.clone(),
                 });
             }
         }
@@ -104,6 +301,24 @@ impl AssetChecker for ERC20Checker {
         asset: PotentialMissingAsset,
         executor: &mut Executor,
     ) -> Result<MissingAssetInfo, eyre::Error> {
+        if let Ok(permit) = permitCall::abi_decode(&asset.calldata) {
+            return Ok(Self::check_permit(&asset, &permit, executor));
+        }
+
+        // `transferFrom` shares its selector with ERC-721; NFTs belong to the
+        // ERC721Checker and would otherwise show up as bogus balances here.
+        if supports_interface(executor, asset.token_address, ERC721_INTERFACE_ID) {
+            return Ok(MissingAssetInfo::new(
+                asset.account,
+                AssetSpec::ERC20 {
+                    token: asset.token_address,
+                    amount: asset.required_amount,
+                },
+                U256::ZERO,
+                U256::ZERO,
+            ));
+        }
+
         // Execute the balanceOf call
         let balance_call = balanceOfCall {
             account: AAddress::from_slice(asset.account.as_slice()),
@@ -124,18 +339,40 @@ impl AssetChecker for ERC20Checker {
             .and_then(|out| balanceOfCall::abi_decode_returns(&out.data()).ok())
             .unwrap_or(U256::ZERO);
 
+        // A rejected authorization blocks the transfer whatever the balance
+        if let Some(authorization) = Authorization::decode(&asset.calldata) {
+            if let Some(reason) =
+                authorization.diagnose(executor, asset.token_address, asset.spender)
+            {
+                debug!(
+                    "authorization of {:?} rejected: {:?}",
+                    asset.account, reason
+                );
+                return Ok(MissingAssetInfo::new(
+                    asset.account,
+                    AssetSpec::ERC20 {
+                        token: asset.token_address,
+                        amount: asset.required_amount,
+                    },
+                    current_balance,
+                    asset.required_amount,
+                )
+                .with_kind(RequirementKind::InvalidAuthorization { reason }));
+            }
+        }
+
         // Calculate missing amount more concisely
         let missing_amount = asset.required_amount.saturating_sub(current_balance);
 
-        Ok(MissingAssetInfo {
-            account: asset.account,
-            required: AssetSpec::ERC20 {
+        Ok(MissingAssetInfo::new(
+            asset.account,
+            AssetSpec::ERC20 {
                 token: asset.token_address,
                 amount: asset.required_amount,
             },
             current_balance,
             missing_amount,
-        })
+        ))
     }
 
     fn deal(
@@ -143,63 +380,75 @@ impl AssetChecker for ERC20Checker {
         recipient: Address,
         asset_spec: AssetSpec,
         executor: &mut Executor,
-        context: &AssetContext,
+        _context: &AssetContext,
     ) -> Result<(), eyre::Error> {
         if let AssetSpec::ERC20 { token, amount } = asset_spec {
-            println!(
+            info!(
                 "Dealing ERC20: token={:?}, recipient={:?}, amount={}",
                 token, recipient, amount
             );
-            println!("Storage accesses found: {:?}", context.storage_accesses);
-
-            if context.storage_accesses.is_empty() {
-                return Err(eyre::eyre!(
-                    "No storage accesses found in trace - cannot determine balance slot"
-                ));
-            }
 
-            let backend = executor.backend_mut();
-            let large_balance = U256::MAX >> 1; // Use a large but not max value
-
-            // Try patching all storage slots that were accessed
-            // This handles cases where balance might be split across multiple slots
-            // or where we need to patch both balance and total supply
-            for (i, &storage_slot) in context.storage_accesses.iter().enumerate() {
-                println!(
-                    "Patching storage slot {} of {}: {:?}",
-                    i + 1,
-                    context.storage_accesses.len(),
-                    storage_slot
-                );
-
-                backend.insert_account_storage(token, storage_slot, large_balance)?;
+            // Record which slots `balanceOf(recipient)` reads, then keep the
+            // one that actually moves the balance (stdstorage-style). Every
+            // other probed slot is restored, so allowances, paused flags or
+            // packed config are never touched.
+            let balance_call = balanceOfCall {
+                account: AAddress::from_slice(recipient.as_slice()),
+            };
+            let reads = record_reads(executor, token, balance_call.abi_encode().into())?;
+            debug!("balanceOf({:?}) reads slots {:?}", recipient, reads.slots);
 
-                println!(
-                    "Successfully patched storage slot {:?} with balance {}",
-                    storage_slot, large_balance
-                );
+            let before = balance_of(executor, token, recipient);
+            if before >= amount {
+                return Ok(()); // Never lower a balance
             }
+            let moves_balance = |executor: &Executor| {
+                let after = balance_of(executor, token, recipient);
+                after != before && after >= amount
+            };
 
-            // Also try to read the balance after patching to verify it worked
-            let balance_call = balanceOfCall {
-                account: AAddress::from_slice(recipient.as_slice()),
+            // A known layout (learned for this code, or bundled for this
+            // token) needs a single write – still verified like a probe.
+            let key = Self::cache_key(executor, reads.reader)?;
+            let known = {
+                let cache = self.slot_cache();
+                key.and_then(|key| cache.get(&key))
+                    .or_else(|| cache.seed(executor.env().cfg.chain_id, token))
             };
-            let balance_data = balance_call.abi_encode();
-
-            match executor.call_raw(recipient, token, balance_data.into(), U256::ZERO) {
-                Ok(balance_result) => {
-                    let new_balance = balance_result
-                        .out
-                        .and_then(|out| balanceOfCall::abi_decode_returns(&out.data()).ok())
-                        .unwrap_or(U256::ZERO);
-                    println!("After patching, balance check shows: {}", new_balance);
-                }
-                Err(e) => {
-                    println!("Warning: Could not verify balance after patching: {}", e);
+            if let Some(known) = known {
+                let slot = known.for_account(recipient);
+                if probe_slots(executor, token, &[slot], |_| Some(amount), &moves_balance)?
+                    .is_some()
+                {
+                    debug!("Balance slot of {:?} served from cache", token);
+                    self.remember(key, known);
+                    return Ok(());
                 }
+                warn!("Cached balance slot of {:?} is stale, probing", token);
             }
 
-            Ok(())
+            let slot = probe_slots(
+                executor,
+                token,
+                &reads.slots,
+                |_| Some(amount),
+                &moves_balance,
+            )?;
+
+            match slot {
+                Some(slot) => {
+                    info!("Balance of {:?} lives in slot {:?}", recipient, slot);
+                    if let Some(layout) = BalanceSlot::derive(slot, recipient) {
+                        self.remember(key, layout);
+                    }
+                    Ok(())
+                }
+                None => Err(AssetSimulatorError::BalanceSlotNotFound {
+                    token,
+                    account: recipient,
+                }
+                .into()),
+            }
         } else {
             Err(eyre::eyre!("ERC20Checker can only deal ERC20 assets"))
         }
@@ -209,3 +458,277 @@ impl AssetChecker for ERC20Checker {
         AssetType::ERC20
     }
 }
+
+fn balance_of(executor: &Executor, token: Address, account: Address) -> U256 {
+    view_call(
+        executor,
+        token,
+        &balanceOfCall {
+            account: AAddress::from_slice(account.as_slice()),
+        },
+    )
+    .unwrap_or(U256::ZERO)
+}
+
+/// Why `permit` was rejected, checked in the order OpenZeppelin's
+/// `ERC20Permit` does. `None` if it looks valid or can't be judged (no
+/// `nonces`/`DOMAIN_SEPARATOR` on the token).
+fn diagnose_permit(
+    executor: &Executor,
+    token: Address,
+    permit: &permitCall,
+) -> Option<PermitFailure> {
+    if permit.deadline < executor.env().block.timestamp {
+        return Some(PermitFailure::ExpiredDeadline);
+    }
+
+    let domain_separator = view_call(executor, token, &DOMAIN_SEPARATORCall {})?;
+    let nonce = view_call(
+        executor,
+        token,
+        &noncesCall {
+            owner: permit.owner,
+        },
+    )?;
+    let owner = Address::from_slice(permit.owner.as_slice());
+    let signed_by_owner = |nonce: U256| {
+        let digest = permit_digest(domain_separator, permit, nonce);
+        ecrecover(executor, digest, permit.v, permit.r.0, permit.s.0) == Some(owner)
+    };
+
+    if signed_by_owner(nonce) {
+        return None;
+    }
+    let stale_or_future = (1..=PERMIT_NONCE_WINDOW)
+        .flat_map(|offset| {
+            let offset = U256::from(offset);
+            [nonce.checked_sub(offset), nonce.checked_add(offset)]
+        })
+        .flatten()
+        .any(signed_by_owner);
+
+    Some(if stale_or_future {
+        PermitFailure::NonceMismatch
+    } else {
+        PermitFailure::InvalidSignature
+    })
+}
+
+// EIP-712 digest of `permit` signed over `nonce`
+fn permit_digest(domain_separator: B256, permit: &permitCall, nonce: U256) -> [u8; 32] {
+    let hash_struct = Permit {
+        owner: permit.owner,
+        spender: permit.spender,
+        value: permit.value,
+        nonce,
+        deadline: permit.deadline,
+    }
+    .eip712_hash_struct();
+    eip712_digest(domain_separator, hash_struct)
+}
+
+fn eip712_digest(domain_separator: B256, hash_struct: B256) -> [u8; 32] {
+    let mut preimage = Vec::with_capacity(66);
+    preimage.extend_from_slice(b"\x19\x01");
+    preimage.extend_from_slice(domain_separator.as_slice());
+    preimage.extend_from_slice(hash_struct.as_slice());
+    keccak256(preimage).0
+}
+
+/// An EIP-3009 authorization, from either entry point.
+struct Authorization {
+    receive: bool, // `receiveWithAuthorization`: only the payee may submit it
+    from: AAddress,
+    to: AAddress,
+    value: U256,
+    valid_after: U256,
+    valid_before: U256,
+    nonce: B256,
+    v: u8,
+    r: B256,
+    s: B256,
+}
+
+impl Authorization {
+    fn decode(data: &[u8]) -> Option<Self> {
+        if let Some(call) = transferWithAuthorizationCall::try_decode(data) {
+            return Some(Self {
+                receive: false,
+                from: call.from,
+                to: call.to,
+                value: call.value,
+                valid_after: call.validAfter,
+                valid_before: call.validBefore,
+                nonce: call.nonce,
+                v: call.v,
+                r: call.r,
+                s: call.s,
+            });
+        }
+        let call = receiveWithAuthorizationCall::try_decode(data)?;
+        Some(Self {
+            receive: true,
+            from: call.from,
+            to: call.to,
+            value: call.value,
+            valid_after: call.validAfter,
+            valid_before: call.validBefore,
+            nonce: call.nonce,
+            v: call.v,
+            r: call.r,
+            s: call.s,
+        })
+    }
+
+    /// Why the token rejects this authorization submitted by `caller`,
+    /// checked in the order Circle's `FiatToken` does. `None` if it looks
+    /// valid or can't be judged.
+    fn diagnose(
+        &self,
+        executor: &Executor,
+        token: Address,
+        caller: Address,
+    ) -> Option<AuthorizationFailure> {
+        if self.receive && caller.as_slice() != self.to.as_slice() {
+            return Some(AuthorizationFailure::CallerNotPayee);
+        }
+
+        let timestamp = executor.env().block.timestamp;
+        if timestamp <= self.valid_after {
+            return Some(AuthorizationFailure::NotYetValid);
+        }
+        if timestamp >= self.valid_before {
+            return Some(AuthorizationFailure::Expired);
+        }
+
+        let used = view_call(
+            executor,
+            token,
+            &authorizationStateCall {
+                authorizer: self.from,
+                nonce: self.nonce,
+            },
+        )?;
+        if used {
+            return Some(AuthorizationFailure::NonceUsed);
+        }
+
+        let domain_separator = view_call(executor, token, &DOMAIN_SEPARATORCall {})?;
+        let digest = eip712_digest(domain_separator, self.hash_struct());
+        let signer = ecrecover(executor, digest, self.v, self.r.0, self.s.0);
+        (signer.map(|signer| signer.as_slice() == self.from.as_slice()) != Some(true))
+            .then_some(AuthorizationFailure::InvalidSignature)
+    }
+
+    fn hash_struct(&self) -> B256 {
+        if self.receive {
+            ReceiveWithAuthorization {
+                from: self.from,
+                to: self.to,
+                value: self.value,
+                validAfter: self.valid_after,
+                validBefore: self.valid_before,
+                nonce: self.nonce,
+            }
+            .eip712_hash_struct()
+        } else {
+            TransferWithAuthorization {
+                from: self.from,
+                to: self.to,
+                value: self.value,
+                validAfter: self.valid_after,
+                validBefore: self.valid_before,
+                nonce: self.nonce,
+            }
+            .eip712_hash_struct()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use forge::revm::primitives::Bytes;
+
+    fn permit_data(owner: Address, spender: Address, value: U256) -> Vec<u8> {
+        permitCall {
+            owner: AAddress::from_slice(owner.as_slice()),
+            spender: AAddress::from_slice(spender.as_slice()),
+            value,
+            deadline: U256::MAX,
+            v: 27,
+            r: B256::repeat_byte(1),
+            s: B256::repeat_byte(2),
+        }
+        .abi_encode()
+    }
+
+    #[test]
+    fn reverted_permit_is_attributed_to_owner() {
+        let owner = Address::repeat_byte(1);
+        let spender = Address::repeat_byte(2);
+        let router = Address::repeat_byte(3);
+        let token = Address::repeat_byte(9);
+
+        let trace = CallTrace {
+            data: Bytes::from(permit_data(owner, spender, U256::from(50))),
+            caller: router,
+            address: token,
+            success: false,
+            ..Default::default()
+        };
+
+        let asset = ERC20Checker::new()
+            .identify_asset(&trace)
+            .expect("should decode permit");
+        assert_eq!(asset.account, owner);
+        assert_eq!(asset.spender, spender);
+        assert_eq!(asset.token_address, token);
+        assert_eq!(asset.required_amount, U256::from(50));
+    }
+
+    #[test]
+    fn successful_permit_is_ignored() {
+        let trace = CallTrace {
+            data: Bytes::from(permit_data(
+                Address::repeat_byte(1),
+                Address::repeat_byte(2),
+                U256::from(50),
+            )),
+            success: true,
+            ..Default::default()
+        };
+
+        assert!(ERC20Checker::new().identify_asset(&trace).is_none());
+    }
+
+    #[test]
+    fn transfer_with_authorization_is_attributed_to_signer() {
+        let from = Address::repeat_byte(1);
+        let relayer = Address::repeat_byte(3);
+
+        let data = transferWithAuthorizationCall {
+            from: AAddress::from_slice(from.as_slice()),
+            to: AAddress::repeat_byte(2),
+            value: U256::from(75),
+            validAfter: U256::ZERO,
+            validBefore: U256::MAX,
+            nonce: B256::repeat_byte(7),
+            v: 27,
+            r: B256::repeat_byte(1),
+            s: B256::repeat_byte(2),
+        }
+        .abi_encode();
+        let trace = CallTrace {
+            data: Bytes::from(data),
+            caller: relayer,
+            ..Default::default()
+        };
+
+        let asset = ERC20Checker::new()
+            .identify_asset(&trace)
+            .expect("should decode transferWithAuthorization");
+        assert_eq!(asset.account, from);
+        assert_eq!(asset.required_amount, U256::from(75));
+    }
+}
@@ -0,0 +1,402 @@
+use alloy_primitives::Address as AAddress;
+use alloy_sol_types::{SolCall, sol};
+use forge::executors::Executor;
+use forge::revm::primitives::{Address, U256};
+use forge::traces::CallTrace;
+use log::{info, warn};
+use std::collections::HashMap;
+
+use crate::simulate::checkers::storage::{accessed_slots, probe_slots};
+use crate::simulate::checkers::traits::{AssetChecker, PotentialMissingAsset};
+use crate::simulate::types::{
+    AssetContext, AssetSpec, AssetType, MissingAssetInfo, RequirementKind,
+};
+use crate::simulate::utils::{supports_interface, transact_as, view_call};
+
+/// ERC-165 interface id of ERC-721.
+pub const ERC721_INTERFACE_ID: [u8; 4] = [0x80, 0xac, 0x58, 0xcd];
+
+sol! {
+    interface IERC721 {
+        function transferFrom(address from, address to, uint256 tokenId) external;
+        function safeTransferFrom(address from, address to, uint256 tokenId) external;
+        function safeTransferFrom(address from, address to, uint256 tokenId, bytes data) external;
+        function approve(address to, uint256 tokenId) external;
+        function setApprovalForAll(address operator, bool approved) external;
+        function ownerOf(uint256 tokenId) external view returns (address);
+        function balanceOf(address owner) external view returns (uint256);
+        function getApproved(uint256 tokenId) external view returns (address);
+        function isApprovedForAll(address owner, address operator) external view returns (bool);
+    }
+}
+
+// NFT operations we know how to check
+enum ERC721Op {
+    Transfer { from: Address, token_id: U256 },
+    Approve { token_id: U256 },
+}
+
+impl ERC721Op {
+    fn decode(data: &[u8]) -> Option<Self> {
+        if let Ok(call) = IERC721::transferFromCall::abi_decode(data) {
+            return Some(Self::Transfer {
+                from: Address::from_slice(call.from.as_slice()),
+                token_id: call.tokenId,
+            });
+        }
+        if let Ok(call) = IERC721::safeTransferFrom_0Call::abi_decode(data) {
+            return Some(Self::Transfer {
+                from: Address::from_slice(call.from.as_slice()),
+                token_id: call.tokenId,
+            });
+        }
+        if let Ok(call) = IERC721::safeTransferFrom_1Call::abi_decode(data) {
+            return Some(Self::Transfer {
+                from: Address::from_slice(call.from.as_slice()),
+                token_id: call.tokenId,
+            });
+        }
+        if let Ok(call) = IERC721::approveCall::abi_decode(data) {
+            return Some(Self::Approve {
+                token_id: call.tokenId,
+            });
+        }
+        None
+    }
+}
+
+// ERC721 checker implementation
+#[derive(Default)]
+pub struct ERC721Checker;
+
+impl ERC721Checker {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl AssetChecker for ERC721Checker {
+    fn identify_asset(&self, trace: &CallTrace) -> Option<PotentialMissingAsset> {
+        let (account, token_id) = match ERC721Op::decode(trace.data.as_ref())? {
+            ERC721Op::Transfer { from, token_id } => (from, token_id),
+            // Only the owner (or one of its operators) may approve
+            ERC721Op::Approve { token_id } => (trace.caller, token_id),
+        };
+
+        Some(PotentialMissingAsset {
+            asset_type: AssetType::ERC721,
+            token_address: trace.address,
+            account,
+            required_amount: U256::from(1),
+            spender: trace.caller,
+            token_ids: vec![token_id],
+            token_amounts: HashMap::new(),
+            calldata: trace.data.clone(),
+        })
+    }
+
+    /// Balances are reported as 1 (holds what's needed) or 0 (doesn't); `kind`
+    /// tells whether ownership or an operator approval is missing.
+    fn check_balance(
+        &self,
+        asset: PotentialMissingAsset,
+        executor: &mut Executor,
+    ) -> Result<MissingAssetInfo, eyre::Error> {
+        let token = asset.token_address;
+        let required = AssetSpec::ERC721 {
+            token,
+            token_ids: asset.token_ids.clone(),
+        };
+
+        // `transferFrom` and `approve` share their selectors with ERC-20
+        if !supports_interface(executor, token, ERC721_INTERFACE_ID) {
+            return Ok(MissingAssetInfo::new(
+                asset.account,
+                required,
+                U256::ZERO,
+                U256::ZERO,
+            ));
+        }
+
+        let mut missing = None;
+        for &token_id in &asset.token_ids {
+            let owner = owner_of(executor, token, token_id);
+            if owner != asset.account {
+                // An operator may act for the owner (e.g. `approve`)
+                if asset.spender == asset.account
+                    && is_approved_for_all(executor, token, owner, asset.spender)
+                {
+                    continue;
+                }
+                missing = Some(RequirementKind::Ownership);
+                break;
+            }
+
+            if asset.spender != asset.account
+                && get_approved(executor, token, token_id) != asset.spender
+                && !is_approved_for_all(executor, token, asset.account, asset.spender)
+            {
+                missing = Some(RequirementKind::Approval {
+                    operator: asset.spender,
+                });
+            }
+        }
+
+        Ok(match missing {
+            Some(kind) => MissingAssetInfo::new(asset.account, required, U256::ZERO, U256::from(1))
+                .with_kind(kind),
+            None => MissingAssetInfo::new(asset.account, required, U256::from(1), U256::ZERO),
+        })
+    }
+
+    fn deal(
+        &self,
+        recipient: Address,
+        asset_spec: AssetSpec,
+        executor: &mut Executor,
+        context: &AssetContext,
+    ) -> Result<(), eyre::Error> {
+        if let AssetSpec::ERC721 { token, token_ids } = asset_spec {
+            for token_id in token_ids {
+                reassign_owner(executor, token, token_id, recipient)?;
+            }
+
+            // Let whoever moves the NFTs on the recipient's behalf do so
+            let operator = context.potential_asset.spender;
+            if operator != recipient && !is_approved_for_all(executor, token, recipient, operator) {
+                approve_operator(executor, token, recipient, operator)?;
+            }
+
+            Ok(())
+        } else {
+            Err(eyre::eyre!("ERC721Checker can only deal ERC721 assets"))
+        }
+    }
+
+    fn asset_type(&self) -> AssetType {
+        AssetType::ERC721
+    }
+}
+
+// ------------------------------------------------------------------------
+//  Views
+// ------------------------------------------------------------------------
+
+fn owner_of(executor: &Executor, token: Address, token_id: U256) -> Address {
+    view_call(executor, token, &IERC721::ownerOfCall { tokenId: token_id })
+        .map(|owner| Address::from_slice(owner.as_slice()))
+        .unwrap_or(Address::ZERO)
+}
+
+fn balance_of(executor: &Executor, token: Address, owner: Address) -> U256 {
+    view_call(
+        executor,
+        token,
+        &IERC721::balanceOfCall {
+            owner: AAddress::from_slice(owner.as_slice()),
+        },
+    )
+    .unwrap_or(U256::ZERO)
+}
+
+fn get_approved(executor: &Executor, token: Address, token_id: U256) -> Address {
+    view_call(
+        executor,
+        token,
+        &IERC721::getApprovedCall { tokenId: token_id },
+    )
+    .map(|approved| Address::from_slice(approved.as_slice()))
+    .unwrap_or(Address::ZERO)
+}
+
+fn is_approved_for_all(
+    executor: &Executor,
+    token: Address,
+    owner: Address,
+    operator: Address,
+) -> bool {
+    view_call(
+        executor,
+        token,
+        &IERC721::isApprovedForAllCall {
+            owner: AAddress::from_slice(owner.as_slice()),
+            operator: AAddress::from_slice(operator.as_slice()),
+        },
+    )
+    .unwrap_or(false)
+}
+
+// ------------------------------------------------------------------------
+//  Dealing
+// ------------------------------------------------------------------------
+
+fn address_mask() -> U256 {
+    (U256::from(1) << 160) - U256::from(1)
+}
+
+fn address_word(address: Address) -> U256 {
+    U256::from_be_slice(address.as_slice())
+}
+
+/// Point the owner slot of `token_id` at `recipient` and move one unit of
+/// `balanceOf` from the previous owner to the recipient.
+fn reassign_owner(
+    executor: &mut Executor,
+    token: Address,
+    token_id: U256,
+    recipient: Address,
+) -> Result<(), eyre::Error> {
+    let previous = owner_of(executor, token, token_id);
+    if previous == recipient {
+        return Ok(());
+    }
+
+    let owner_call = IERC721::ownerOfCall { tokenId: token_id };
+    let slots = accessed_slots(executor, token, owner_call.abi_encode().into())?;
+
+    // Owners live in the low 160 bits, possibly packed with other data
+    // (ERC721A); keep the upper bits untouched.
+    let mask = address_mask();
+    let found = probe_slots(
+        executor,
+        token,
+        &slots,
+        |current| {
+            let holds_previous =
+                previous == Address::ZERO || current & mask == address_word(previous);
+            holds_previous.then(|| (current & !mask) | address_word(recipient))
+        },
+        |executor| owner_of(executor, token, token_id) == recipient,
+    )?;
+    if found.is_none() {
+        return Err(eyre::eyre!(
+            "no owner slot found for token id {} of {:?}",
+            token_id,
+            token
+        ));
+    }
+    info!(
+        "Reassigned ERC721 {:?} #{} from {:?} to {:?}",
+        token, token_id, previous, recipient
+    );
+
+    if previous != Address::ZERO {
+        adjust_balance(executor, token, previous, false)?;
+    }
+    adjust_balance(executor, token, recipient, true)
+}
+
+/// Bump `balanceOf(owner)` up or down by one.
+fn adjust_balance(
+    executor: &mut Executor,
+    token: Address,
+    owner: Address,
+    increment: bool,
+) -> Result<(), eyre::Error> {
+    let current = balance_of(executor, token, owner);
+    let target = if increment {
+        current + U256::from(1)
+    } else if current.is_zero() {
+        return Ok(());
+    } else {
+        current - U256::from(1)
+    };
+
+    let balance_call = IERC721::balanceOfCall {
+        owner: AAddress::from_slice(owner.as_slice()),
+    };
+    let slots = accessed_slots(executor, token, balance_call.abi_encode().into())?;
+
+    // Shift the whole word so balances packed into the low bits work too
+    let found = probe_slots(
+        executor,
+        token,
+        &slots,
+        |value| value.checked_sub(current)?.checked_add(target),
+        |executor| balance_of(executor, token, owner) == target,
+    )?;
+    if found.is_none() {
+        warn!(
+            "Could not keep balanceOf({:?}) consistent on {:?}",
+            owner, token
+        );
+    }
+    Ok(())
+}
+
+/// Impersonate `owner` to call `setApprovalForAll(operator, true)`.
+fn approve_operator(
+    executor: &mut Executor,
+    token: Address,
+    owner: Address,
+    operator: Address,
+) -> Result<(), eyre::Error> {
+    let approval = IERC721::setApprovalForAllCall {
+        operator: AAddress::from_slice(operator.as_slice()),
+        approved: true,
+    };
+
+    let result = transact_as(executor, owner, token, approval.abi_encode())?;
+    if result.reverted {
+        return Err(eyre::eyre!(
+            "setApprovalForAll({:?}) reverted on {:?}",
+            operator,
+            token
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use forge::revm::primitives::Bytes;
+
+    fn trace_with(data: Vec<u8>, caller: Address, address: Address) -> CallTrace {
+        CallTrace {
+            data: Bytes::from(data),
+            caller,
+            address,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn identifies_safe_transfer_with_data() {
+        let from = Address::repeat_byte(1);
+        let operator = Address::repeat_byte(3);
+        let collection = Address::repeat_byte(9);
+
+        let data = IERC721::safeTransferFrom_1Call {
+            from: AAddress::from_slice(from.as_slice()),
+            to: AAddress::repeat_byte(2),
+            tokenId: U256::from(12),
+            data: Default::default(),
+        }
+        .abi_encode();
+
+        let asset = ERC721Checker::new()
+            .identify_asset(&trace_with(data, operator, collection))
+            .expect("should decode safeTransferFrom");
+        assert_eq!(asset.account, from);
+        assert_eq!(asset.spender, operator);
+        assert_eq!(asset.token_address, collection);
+        assert_eq!(asset.token_ids, vec![U256::from(12)]);
+    }
+
+    #[test]
+    fn approve_is_attributed_to_caller() {
+        let owner = Address::repeat_byte(1);
+        let data = IERC721::approveCall {
+            to: AAddress::repeat_byte(2),
+            tokenId: U256::from(7),
+        }
+        .abi_encode();
+
+        let asset = ERC721Checker::new()
+            .identify_asset(&trace_with(data, owner, Address::repeat_byte(9)))
+            .expect("should decode approve");
+        assert_eq!(asset.account, owner);
+        assert_eq!(asset.token_ids, vec![U256::from(7)]);
+    }
+}
@@ -1,6 +1,17 @@
+pub mod allowance;
+pub mod erc1155;
 pub mod erc20;
+pub mod erc721;
+pub mod native;
+pub mod slot_cache;
+pub mod storage;
 pub mod traits;
 
+pub use allowance::AllowanceChecker;
 pub use erc20::ERC20Checker;
+pub use erc721::ERC721Checker;
+pub use erc1155::ERC1155Checker;
+pub use native::NativeChecker;
+pub use slot_cache::SlotCache;
 pub use traits::AssetChecker;
 pub use traits::PotentialMissingAsset;
@@ -0,0 +1,92 @@
+use forge::executors::Executor;
+use forge::revm::interpreter::InstructionResult;
+use forge::revm::primitives::{Address, U256};
+use forge::traces::{CallKind, CallTrace};
+use std::collections::HashMap;
+
+use crate::simulate::checkers::traits::{AssetChecker, PotentialMissingAsset};
+use crate::simulate::types::{AssetContext, AssetSpec, AssetType, MissingAssetInfo};
+
+/// Native balance the sender needs before revm will even start executing:
+/// `gas_limit * gas_price + value`.
+pub fn upfront_cost(gas_limit: u64, gas_price: U256, value: U256) -> U256 {
+    U256::from(gas_limit)
+        .saturating_mul(gas_price)
+        .saturating_add(value)
+}
+
+// Native asset (ETH) checker
+#[derive(Default)]
+pub struct NativeChecker;
+
+impl NativeChecker {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl AssetChecker for NativeChecker {
+    fn identify_asset(&self, trace: &CallTrace) -> Option<PotentialMissingAsset> {
+        // Delegate calls inherit msg.value, they don't move it
+        if trace.value.is_zero() || trace.kind == CallKind::DelegateCall {
+            return None;
+        }
+
+        // The top-level value always comes from the sender; nested value
+        // transfers only matter when they failed for lack of funds.
+        if trace.depth > 0 && trace.status != InstructionResult::OutOfFunds {
+            return None;
+        }
+
+        Some(PotentialMissingAsset {
+            asset_type: AssetType::Native,
+            token_address: Address::ZERO,
+            account: trace.caller,
+            required_amount: trace.value,
+            spender: trace.caller,
+            token_ids: Vec::new(),
+            token_amounts: HashMap::new(),
+            calldata: trace.data.clone(),
+        })
+    }
+
+    fn check_balance(
+        &self,
+        asset: PotentialMissingAsset,
+        executor: &mut Executor,
+    ) -> Result<MissingAssetInfo, eyre::Error> {
+        let current_balance = executor.get_balance(asset.account)?;
+        let missing_amount = asset.required_amount.saturating_sub(current_balance);
+
+        Ok(MissingAssetInfo::new(
+            asset.account,
+            AssetSpec::Native(asset.required_amount),
+            current_balance,
+            missing_amount,
+        ))
+    }
+
+    fn deal(
+        &self,
+        recipient: Address,
+        asset_spec: AssetSpec,
+        executor: &mut Executor,
+        _context: &AssetContext,
+    ) -> Result<(), eyre::Error> {
+        if let AssetSpec::Native(amount) = asset_spec {
+            // Never lower a balance: the account may need more than this
+            // single transfer for the rest of the transaction.
+            let current = executor.get_balance(recipient)?;
+            if current < amount {
+                executor.set_balance(recipient, amount)?;
+            }
+            Ok(())
+        } else {
+            Err(eyre::eyre!("NativeChecker can only deal native assets"))
+        }
+    }
+
+    fn asset_type(&self) -> AssetType {
+        AssetType::Native
+    }
+}
@@ -0,0 +1,214 @@
+//! Cache of ERC-20 balance mapping slots.
+//!
+//! Entries are keyed by chain id and the code hash of the contract whose code
+//! reads the balance, so every proxy or clone of the same implementation
+//! shares one entry. A small seed list of well-known tokens (by address) is
+//! bundled so common tokens never need probing.
+
+use forge::revm::primitives::{Address, B256, U256, b256, keccak256};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Bundled seed entries, see `balance_slots.json`.
+const SEED_SLOTS: &str = include_str!("balance_slots.json");
+
+/// Base slot of OpenZeppelin v5's namespaced (ERC-7201) `ERC20Storage`.
+const OZ_ERC20_STORAGE: B256 =
+    b256!("52c63247e1f47db19d5ce0460030c497f067ca4cebf71ba98eeadabe20bace00");
+
+/// Plain storage slots searched when deriving a mapping's base slot.
+const MAX_DERIVED_BASE: u64 = 255;
+
+/// How a `mapping(address => uint256)` entry is located.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MappingLayout {
+    /// `keccak256(key . base)` – Solidity
+    Solidity,
+    /// `keccak256(base . key)` – Vyper
+    Vyper,
+}
+
+/// Location of a token's balance mapping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BalanceSlot {
+    pub slot: U256,
+    pub layout: MappingLayout,
+}
+
+impl BalanceSlot {
+    /// Storage slot holding the balance of `account`.
+    pub fn for_account(&self, account: Address) -> U256 {
+        let key = account.into_word();
+        let base = B256::from(self.slot.to_be_bytes::<32>());
+
+        let mut preimage = [0u8; 64];
+        let (first, second) = match self.layout {
+            MappingLayout::Solidity => (key, base),
+            MappingLayout::Vyper => (base, key),
+        };
+        preimage[..32].copy_from_slice(first.as_slice());
+        preimage[32..].copy_from_slice(second.as_slice());
+
+        U256::from_be_bytes(keccak256(preimage).0)
+    }
+
+    /// Recover the mapping that stores `account`'s balance in `slot`, if it is
+    /// a plain mapping at a low base slot or at OpenZeppelin's ERC-7201 base.
+    pub fn derive(slot: U256, account: Address) -> Option<Self> {
+        let bases = (0..=MAX_DERIVED_BASE)
+            .map(U256::from)
+            .chain(std::iter::once(U256::from_be_bytes(OZ_ERC20_STORAGE.0)));
+
+        for base in bases {
+            for layout in [MappingLayout::Solidity, MappingLayout::Vyper] {
+                let candidate = Self { slot: base, layout };
+                if candidate.for_account(account) == slot {
+                    return Some(candidate);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SlotCacheKey {
+    pub chain_id: u64,
+    pub code_hash: B256,
+}
+
+// On-disk representation of a learned entry
+#[derive(Serialize, Deserialize)]
+struct CachedEntry {
+    chain_id: u64,
+    code_hash: B256,
+    #[serde(flatten)]
+    slot: BalanceSlot,
+}
+
+// On-disk representation of a seed entry
+#[derive(Serialize, Deserialize)]
+struct SeedEntry {
+    chain_id: u64,
+    token: Address,
+    #[serde(flatten)]
+    slot: BalanceSlot,
+}
+
+/// In-memory slot cache, optionally persisted to a JSON file.
+#[derive(Debug, Default)]
+pub struct SlotCache {
+    entries: HashMap<SlotCacheKey, BalanceSlot>,
+    seeds: HashMap<(u64, Address), BalanceSlot>,
+    path: Option<PathBuf>,
+}
+
+impl SlotCache {
+    /// Empty cache pre-loaded with the bundled well-known tokens.
+    pub fn new() -> Self {
+        let seeds: Vec<SeedEntry> =
+            serde_json::from_str(SEED_SLOTS).expect("bundled balance_slots.json is valid");
+
+        Self {
+            entries: HashMap::new(),
+            seeds: seeds
+                .into_iter()
+                .map(|seed| ((seed.chain_id, seed.token), seed.slot))
+                .collect(),
+            path: None,
+        }
+    }
+
+    /// Cache backed by `path`: existing entries are loaded and every newly
+    /// learned slot is written back. A missing file starts out empty.
+    pub fn with_file(path: impl AsRef<Path>) -> Result<Self, eyre::Error> {
+        let path = path.as_ref().to_path_buf();
+        let mut cache = Self::new();
+
+        if path.exists() {
+            let stored: Vec<CachedEntry> = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+            for entry in stored {
+                let key = SlotCacheKey {
+                    chain_id: entry.chain_id,
+                    code_hash: entry.code_hash,
+                };
+                cache.entries.insert(key, entry.slot);
+            }
+        }
+
+        cache.path = Some(path);
+        Ok(cache)
+    }
+
+    pub fn get(&self, key: &SlotCacheKey) -> Option<BalanceSlot> {
+        self.entries.get(key).copied()
+    }
+
+    /// Bundled entry for a well-known token, if any.
+    pub fn seed(&self, chain_id: u64, token: Address) -> Option<BalanceSlot> {
+        self.seeds.get(&(chain_id, token)).copied()
+    }
+
+    /// Remember `slot` and persist the cache if it is file-backed.
+    pub fn insert(&mut self, key: SlotCacheKey, slot: BalanceSlot) -> Result<(), eyre::Error> {
+        if self.entries.insert(key, slot) == Some(slot) {
+            return Ok(());
+        }
+        self.save()
+    }
+
+    /// Write all learned entries to the backing file (no-op when in-memory).
+    pub fn save(&self) -> Result<(), eyre::Error> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        // Sorted so the file diffs cleanly between runs
+        let mut stored: Vec<CachedEntry> = self
+            .entries
+            .iter()
+            .map(|(key, slot)| CachedEntry {
+                chain_id: key.chain_id,
+                code_hash: key.code_hash,
+                slot: *slot,
+            })
+            .collect();
+        stored.sort_by_key(|entry| (entry.chain_id, entry.code_hash));
+
+        std::fs::write(path, serde_json::to_string_pretty(&stored)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn derives_the_base_slot_it_was_built_from() {
+        let account = Address::repeat_byte(7);
+        for layout in [MappingLayout::Solidity, MappingLayout::Vyper] {
+            let known = BalanceSlot {
+                slot: U256::from(9),
+                layout,
+            };
+            assert_eq!(
+                BalanceSlot::derive(known.for_account(account), account),
+                Some(known)
+            );
+        }
+    }
+
+    #[test]
+    fn bundled_seeds_cover_base_usdc() {
+        let usdc = Address::from_str("0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913").unwrap();
+        let seed = SlotCache::new()
+            .seed(8453, usdc)
+            .expect("USDC on Base is seeded");
+        assert_eq!(seed.slot, U256::from(9));
+        assert_eq!(seed.layout, MappingLayout::Solidity);
+    }
+}
@@ -0,0 +1,87 @@
+//! Storage-slot discovery in the spirit of forge-std's `stdstorage`:
+//! record which slots a view call reads, then find the one that matters by
+//! writing to it and re-running the view.
+
+use forge::executors::Executor;
+use forge::revm::DatabaseRef;
+use forge::revm::primitives::{Address, Bytes, U256};
+
+use crate::simulate::types::AssetContext;
+
+/// Storage reads performed by a view call.
+#[derive(Debug, Clone)]
+pub struct StorageReads {
+    /// Slots read (SLOAD), in first-access order and without duplicates.
+    pub slots: Vec<U256>,
+    /// Address whose code did the reading: the implementation behind a proxy,
+    /// or the target itself.
+    pub reader: Address,
+}
+
+/// Record the storage reads of executing `calldata` against `target`.
+pub fn record_reads(
+    executor: &Executor,
+    target: Address,
+    calldata: Bytes,
+) -> Result<StorageReads, eyre::Error> {
+    let result = executor.call_raw(Address::ZERO, target, calldata, U256::ZERO)?;
+
+    let mut reads = StorageReads {
+        slots: Vec::new(),
+        reader: target,
+    };
+    if let Some(traces) = result.traces {
+        for node in traces.nodes() {
+            let slots = AssetContext::extract_storage_accesses(&node.trace);
+            if !slots.is_empty() {
+                // Delegate-call frames report the implementation as address
+                reads.reader = node.trace.address;
+            }
+            for slot in slots {
+                if !reads.slots.contains(&slot) {
+                    reads.slots.push(slot);
+                }
+            }
+        }
+    }
+    Ok(reads)
+}
+
+/// Storage slots read (SLOAD) while executing `calldata` against `target`,
+/// in first-access order and without duplicates.
+pub fn accessed_slots(
+    executor: &Executor,
+    target: Address,
+    calldata: Bytes,
+) -> Result<Vec<U256>, eyre::Error> {
+    Ok(record_reads(executor, target, calldata)?.slots)
+}
+
+/// Try the `slots` of `target` one by one: write `candidate(current_value)`
+/// and keep the first slot for which `verify` holds. Every rejected slot is
+/// restored to its original value, so no unrelated state is left corrupted.
+pub fn probe_slots(
+    executor: &mut Executor,
+    target: Address,
+    slots: &[U256],
+    mut candidate: impl FnMut(U256) -> Option<U256>,
+    mut verify: impl FnMut(&Executor) -> bool,
+) -> Result<Option<U256>, eyre::Error> {
+    for &slot in slots {
+        let original = executor.backend().storage_ref(target, slot)?;
+        let Some(value) = candidate(original) else {
+            continue;
+        };
+
+        executor
+            .backend_mut()
+            .insert_account_storage(target, slot, value)?;
+        if verify(executor) {
+            return Ok(Some(slot));
+        }
+        executor
+            .backend_mut()
+            .insert_account_storage(target, slot, original)?;
+    }
+    Ok(None)
+}
@@ -1,7 +1,8 @@
 use crate::simulate::types::{AssetContext, AssetSpec, AssetType, MissingAssetInfo};
 use forge::executors::Executor;
-use forge::revm::primitives::{Address, U256};
+use forge::revm::primitives::{Address, Bytes, U256};
 use forge::traces::CallTrace;
+use std::collections::HashMap;
 
 #[derive(Debug, Clone)]
 pub struct PotentialMissingAsset {
@@ -9,6 +10,10 @@ pub struct PotentialMissingAsset {
     pub token_address: Address,
     pub account: Address,
     pub required_amount: U256,
+    pub spender: Address,     // msg.sender of the token call (for approvals)
+    pub token_ids: Vec<U256>, // Non-fungible ids involved, if any
+    pub token_amounts: HashMap<U256, U256>, // Multi-token id → amount, if any
+    pub calldata: Bytes,      // Input of the identified call
 }
 
 // Core trait for checking a specific asset type
@@ -23,7 +28,18 @@ pub trait AssetChecker {
         executor: &mut Executor,
     ) -> Result<MissingAssetInfo, eyre::Error>;
 
-    // Third phase: deal assets to fix missing balances (like Foundry's deal)
+    // Same as `check_balance`, for checkers that find several shortfalls in
+    // one call (e.g. one per ERC-1155 id); this is what discovery calls
+    fn check_balances(
+        &self,
+        asset: PotentialMissingAsset,
+        executor: &mut Executor,
+    ) -> Result<Vec<MissingAssetInfo>, eyre::Error> {
+        Ok(vec![self.check_balance(asset, executor)?])
+    }
+
+    // Third phase: deal assets to fix missing balances (like Foundry's deal,
+    // but what the recipient already holds is never lowered)
     fn deal(
         &self,
         recipient: Address,
@@ -34,4 +50,10 @@ pub trait AssetChecker {
 
     // Helper to get the asset type this checker handles
     fn asset_type(&self) -> AssetType;
+
+    // Whether `deal` sets holdings of `asset_type()` (used for grants) rather
+    // than something around them, like an allowance
+    fn deals_holdings(&self) -> bool {
+        true
+    }
 }
@@ -1,10 +1,17 @@
 //! Public error type for the simulator (work-in-progress).
 
+use forge::revm::primitives::Address;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum AssetSimulatorError {
     #[error("executor initialisation failed: {0}")]
     ExecutorInit(String),
+
+    #[error("no storage slot of {token:?} controls balanceOf({account:?})")]
+    BalanceSlotNotFound { token: Address, account: Address },
+
+    #[error("no snapshot named {0:?}")]
+    UnknownSnapshot(String),
     // add concrete variants as the API stabilises …
 }
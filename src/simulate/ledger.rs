@@ -0,0 +1,310 @@
+//! Asset movements of a successful run, rebuilt from its trace: ERC-20 /
+//! ERC-721 / ERC-1155 `Transfer*` events, WETH-style `Deposit`/`Withdrawal`
+//! and native value carried by calls, all in execution order.
+
+use alloy_primitives::{Address as AAddress, B256};
+use alloy_sol_types::{SolEvent, sol};
+use forge::executors::Executor;
+use forge::revm::primitives::{Address, U256};
+use forge::traces::{CallKind, CallTraceNode, SparsedTraceArena};
+use std::collections::HashMap;
+
+use crate::simulate::checkers::erc20::balanceOfCall;
+use crate::simulate::checkers::erc721::IERC721;
+use crate::simulate::checkers::erc1155::IERC1155;
+use crate::simulate::types::{AssetSpec, TraceFrame};
+use crate::simulate::utils::view_call;
+
+sol! {
+    // ERC-20 and ERC-721 share this signature; ERC-721 indexes the third argument
+    event Transfer(address indexed from, address indexed to, uint256 value);
+    event TransferSingle(address indexed operator, address indexed from, address indexed to, uint256 id, uint256 value);
+    event TransferBatch(address indexed operator, address indexed from, address indexed to, uint256[] ids, uint256[] values);
+    // WETH mints and burns without a Transfer event
+    event Deposit(address indexed dst, uint256 wad);
+    event Withdrawal(address indexed src, uint256 wad);
+}
+
+/// A single unit of account: one token balance, or one non-fungible id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Holding {
+    Native,
+    ERC20 { token: Address },
+    ERC721 { token: Address, id: U256 },
+    ERC1155 { token: Address, id: U256 },
+}
+
+impl Holding {
+    /// `amount` of this holding as an asset spec.
+    pub fn spec(&self, amount: U256) -> AssetSpec {
+        match *self {
+            Holding::Native => AssetSpec::Native(amount),
+            Holding::ERC20 { token } => AssetSpec::ERC20 { token, amount },
+            Holding::ERC721 { token, id } => AssetSpec::ERC721 {
+                token,
+                token_ids: vec![id],
+            },
+            Holding::ERC1155 { token, id } => AssetSpec::ERC1155 {
+                token,
+                token_amounts: HashMap::from([(id, amount)]),
+            },
+        }
+    }
+
+    /// What `account` holds of this right now (1/0 for an ERC-721 id).
+    pub fn balance_of(&self, executor: &Executor, account: Address) -> U256 {
+        let owner = AAddress::from_slice(account.as_slice());
+        let balance = match *self {
+            Holding::Native => executor.get_balance(account).ok(),
+            Holding::ERC20 { token } => {
+                view_call(executor, token, &balanceOfCall { account: owner })
+            }
+            Holding::ERC721 { token, id } => {
+                view_call(executor, token, &IERC721::ownerOfCall { tokenId: id })
+                    .map(|holder| U256::from((holder == owner) as u8))
+            }
+            Holding::ERC1155 { token, id } => view_call(
+                executor,
+                token,
+                &IERC1155::balanceOfCall { account: owner, id },
+            ),
+        };
+        balance.unwrap_or(U256::ZERO)
+    }
+}
+
+/// Outflows of one account for one holding over the whole transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outflow {
+    pub account: Address,
+    pub holding: Holding,
+    pub gross: U256,               // Everything sent, ignoring what came in
+    pub net: U256,                 // Peak of (sent - received) so far: what must be held upfront
+    pub frame: Option<TraceFrame>, // Frame of the movement that hit the peak
+}
+
+#[derive(Default)]
+struct Entry {
+    sent: U256,
+    received: U256,
+    peak: U256,
+    frame: Option<TraceFrame>,
+}
+
+/// Running per-account totals, fed movements in execution order.
+#[derive(Default)]
+pub struct Ledger {
+    entries: HashMap<(Address, Holding), Entry>,
+}
+
+impl Ledger {
+    /// Ledger of every movement in a successful run's `traces`.
+    pub fn from_traces(traces: &SparsedTraceArena) -> Self {
+        let mut ledger = Self::default();
+        ledger.record_traces(traces);
+        ledger
+    }
+
+    /// Add the movements of another successful run that came after those
+    /// already recorded, e.g. the next call of a bundle.
+    pub fn record_traces(&mut self, traces: &SparsedTraceArena) {
+        let nodes = traces.nodes();
+        if let Some(root) = nodes.first() {
+            self.walk(nodes, root, root.trace.address);
+        }
+    }
+
+    /// Peak net outflow of `account` for `holding`, if it sent any.
+    pub fn net_outflow(&self, account: Address, holding: Holding) -> Option<U256> {
+        self.entries
+            .get(&(account, holding))
+            .filter(|entry| entry.sent > U256::ZERO)
+            .map(|entry| entry.peak)
+    }
+
+    /// Every account that sent something, sorted for stable output.
+    pub fn outflows(&self) -> Vec<Outflow> {
+        let mut outflows: Vec<Outflow> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.sent > U256::ZERO)
+            .map(|(&(account, holding), entry)| Outflow {
+                account,
+                holding,
+                gross: entry.sent,
+                net: entry.peak,
+                frame: entry.frame,
+            })
+            .collect();
+        outflows.sort_by_key(|outflow| (outflow.account, outflow.holding));
+        outflows
+    }
+
+    // Value moves when a frame starts; its logs are interleaved with its
+    // children by their `position`. Reverted frames leave no trace.
+    fn walk(&mut self, nodes: &[CallTraceNode], node: &CallTraceNode, context: Address) {
+        let trace = &node.trace;
+        if !trace.success {
+            return;
+        }
+        let frame = TraceFrame::from_node(node);
+
+        // Delegate calls run in (and emit logs as) the caller's context
+        let context = match trace.kind {
+            CallKind::DelegateCall | CallKind::CallCode => context,
+            _ => trace.address,
+        };
+        if trace.value > U256::ZERO
+            && !matches!(
+                trace.kind,
+                CallKind::DelegateCall | CallKind::CallCode | CallKind::StaticCall
+            )
+        {
+            self.record(
+                trace.caller,
+                trace.address,
+                Holding::Native,
+                trace.value,
+                frame,
+            );
+        }
+
+        for position in 0..=node.children.len() {
+            for log in node
+                .logs
+                .iter()
+                .filter(|log| log.position as usize == position)
+            {
+                let topics: Vec<B256> = log
+                    .raw_log
+                    .topics()
+                    .iter()
+                    .map(|topic| B256::from(topic.0))
+                    .collect();
+                self.record_log(context, &topics, &log.raw_log.data, frame);
+            }
+            if let Some(&child) = node.children.get(position) {
+                self.walk(nodes, &nodes[child], context);
+            }
+        }
+    }
+
+    fn record_log(&mut self, emitter: Address, topics: &[B256], data: &[u8], frame: TraceFrame) {
+        let Some(&signature) = topics.first() else {
+            return;
+        };
+        let address = |topic: &B256| Address::from_slice(&topic[12..]);
+
+        if signature == Transfer::SIGNATURE_HASH {
+            match (topics.len(), data.len()) {
+                (3, 32) => self.record(
+                    address(&topics[1]),
+                    address(&topics[2]),
+                    Holding::ERC20 { token: emitter },
+                    U256::from_be_slice(data),
+                    frame,
+                ),
+                (4, 0) => self.record(
+                    address(&topics[1]),
+                    address(&topics[2]),
+                    Holding::ERC721 {
+                        token: emitter,
+                        id: U256::from_be_bytes(topics[3].0),
+                    },
+                    U256::from(1),
+                    frame,
+                ),
+                _ => {}
+            }
+        } else if let Ok(event) = TransferSingle::decode_raw_log(topics.iter().copied(), data) {
+            self.record(
+                Address::from_slice(event.from.as_slice()),
+                Address::from_slice(event.to.as_slice()),
+                Holding::ERC1155 {
+                    token: emitter,
+                    id: event.id,
+                },
+                event.value,
+                frame,
+            );
+        } else if let Ok(event) = TransferBatch::decode_raw_log(topics.iter().copied(), data) {
+            let from = Address::from_slice(event.from.as_slice());
+            let to = Address::from_slice(event.to.as_slice());
+            for (id, value) in event.ids.into_iter().zip(event.values) {
+                let holding = Holding::ERC1155 { token: emitter, id };
+                self.record(from, to, holding, value, frame);
+            }
+        } else if let Ok(event) = Deposit::decode_raw_log(topics.iter().copied(), data) {
+            let dst = Address::from_slice(event.dst.as_slice());
+            let holding = Holding::ERC20 { token: emitter };
+            self.record(Address::ZERO, dst, holding, event.wad, frame);
+        } else if let Ok(event) = Withdrawal::decode_raw_log(topics.iter().copied(), data) {
+            let src = Address::from_slice(event.src.as_slice());
+            let holding = Holding::ERC20 { token: emitter };
+            self.record(src, Address::ZERO, holding, event.wad, frame);
+        }
+    }
+
+    // The zero address stands in for mints and burns and is never tracked
+    fn record(
+        &mut self,
+        from: Address,
+        to: Address,
+        holding: Holding,
+        amount: U256,
+        frame: TraceFrame,
+    ) {
+        if amount.is_zero() || from == to {
+            return;
+        }
+        if from != Address::ZERO {
+            let entry = self.entries.entry((from, holding)).or_default();
+            entry.sent = entry.sent.saturating_add(amount);
+            let deficit = entry.sent.saturating_sub(entry.received);
+            if deficit > entry.peak {
+                entry.peak = deficit;
+                entry.frame = Some(frame);
+            }
+        }
+        if to != Address::ZERO {
+            let entry = self.entries.entry((to, holding)).or_default();
+            entry.received = entry.received.saturating_add(amount);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame() -> TraceFrame {
+        TraceFrame {
+            index: 0,
+            depth: 0,
+            address: Address::ZERO,
+        }
+    }
+
+    #[test]
+    fn respending_received_tokens_needs_only_the_peak() {
+        let user = Address::repeat_byte(1);
+        let pool = Address::repeat_byte(2);
+        let holding = Holding::ERC20 {
+            token: Address::repeat_byte(9),
+        };
+
+        let mut ledger = Ledger::default();
+        // Pay 100, get 60 back, pay 60 again
+        ledger.record(user, pool, holding, U256::from(100), frame());
+        ledger.record(pool, user, holding, U256::from(60), frame());
+        ledger.record(user, pool, holding, U256::from(60), frame());
+
+        let outflow = ledger
+            .outflows()
+            .into_iter()
+            .find(|outflow| outflow.account == user)
+            .unwrap();
+        assert_eq!(outflow.gross, U256::from(160));
+        assert_eq!(outflow.net, U256::from(100));
+    }
+}
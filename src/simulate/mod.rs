@@ -1,11 +1,32 @@
 pub use self::asset_simulator::AssetSimulator;
+pub use self::blocker::BlockerCategory;
+pub use self::checkers::allowance::AllowanceChecker;
 pub use self::checkers::erc20::ERC20Checker;
+pub use self::checkers::erc721::ERC721Checker;
+pub use self::checkers::erc1155::ERC1155Checker;
+pub use self::checkers::native::NativeChecker;
+pub use self::checkers::slot_cache::SlotCache;
 pub use self::checkers::traits::{AssetChecker, PotentialMissingAsset};
-pub use self::types::{AssetType, Call, ForkInfo, MissingAssetInfo};
+pub use self::ledger::{Holding, Outflow};
+pub use self::overrides::{AccountOverride, StateOverride, parse_state_override};
+pub use self::report::{
+    BundleReport, DiscoveryReport, DiscoveryStatus, Iteration, StepRequirement,
+};
+pub use self::time_warp::TimeRequirement;
+pub use self::types::{
+    AssetGrant, AssetType, AuthorizationFailure, Call, CheckOptions, DealStrategy, DiscoveryMode,
+    ForkInfo, MissingAssetInfo, PermitFailure, RequirementKind,
+};
 
 pub mod asset_simulator;
+pub mod blocker;
 pub mod builder;
 pub mod checkers;
 pub mod error;
+pub mod ledger;
+pub mod overrides;
+pub mod reconcile;
+pub mod report;
+pub mod time_warp;
 pub mod types;
 pub mod utils;
@@ -0,0 +1,138 @@
+//! Geth-style state overrides (the `stateOverride` argument of `eth_call`),
+//! applied to the backend before a check runs.
+
+use forge::executors::Executor;
+use forge::revm::primitives::{Address, B256, Bytecode, Bytes, U64, U256};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Overrides per address, as sent by wallets and dapp backends.
+pub type StateOverride = HashMap<Address, AccountOverride>;
+
+/// What to replace on one account before simulating. Fields other nodes
+/// support but this simulator doesn't (e.g. `movePrecompileToAddress`) are
+/// accepted and ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountOverride {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub balance: Option<U256>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<U64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<Bytes>,
+    /// Replaces the whole storage of the account.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<HashMap<B256, B256>>,
+    /// Replaces only the given slots.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state_diff: Option<HashMap<B256, B256>>,
+}
+
+/// Parse a `stateOverride` object from its standard JSON shape.
+pub fn parse_state_override(json: &str) -> Result<StateOverride, serde_json::Error> {
+    serde_json::from_str(json)
+}
+
+/// Write `overrides` into `executor`'s backend.
+pub(crate) fn apply_state_override(
+    overrides: &StateOverride,
+    executor: &mut Executor,
+) -> Result<(), eyre::Error> {
+    for (&address, account) in overrides {
+        if account.state.is_some() && account.state_diff.is_some() {
+            return Err(eyre::eyre!(
+                "state override for {:?} sets both state and stateDiff",
+                address
+            ));
+        }
+
+        if let Some(balance) = account.balance {
+            executor.set_balance(address, balance)?;
+        }
+        if let Some(nonce) = account.nonce {
+            executor.set_nonce(address, nonce.to())?;
+        }
+        if let Some(code) = &account.code {
+            executor.set_code(address, Bytecode::new_raw(code.clone()))?;
+        }
+        if let Some(state) = &account.state {
+            let storage = state
+                .iter()
+                .map(|(slot, value)| (slot_key(slot), slot_key(value)))
+                .collect();
+            executor
+                .backend_mut()
+                .replace_account_storage(address, storage)?;
+        }
+        if let Some(state_diff) = &account.state_diff {
+            for (slot, value) in state_diff {
+                executor.backend_mut().insert_account_storage(
+                    address,
+                    slot_key(slot),
+                    slot_key(value),
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn slot_key(word: &B256) -> U256 {
+    U256::from_be_bytes(word.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_standard_shape() {
+        let overrides = parse_state_override(
+            r#"{
+                "0x1000000000000000000000000000000000000001": {
+                    "balance": "0xde0b6b3a7640000",
+                    "nonce": "0x5",
+                    "code": "0x6001",
+                    "stateDiff": {
+                        "0x0000000000000000000000000000000000000000000000000000000000000002": "0x0000000000000000000000000000000000000000000000000000000000000064"
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let address: Address = "0x1000000000000000000000000000000000000001"
+            .parse()
+            .unwrap();
+        let account = &overrides[&address];
+        assert_eq!(
+            account.balance,
+            Some(U256::from(1_000_000_000_000_000_000u64))
+        );
+        assert_eq!(account.nonce, Some(U64::from(5)));
+        assert_eq!(account.code, Some(Bytes::from_static(&[0x60, 0x01])));
+        assert!(account.state.is_none());
+        let diff = account.state_diff.as_ref().unwrap();
+        assert_eq!(
+            diff.values().map(slot_key).collect::<Vec<_>>(),
+            vec![U256::from(100)]
+        );
+    }
+
+    #[test]
+    fn ignores_unsupported_fields() {
+        let overrides = parse_state_override(
+            r#"{
+                "0x0000000000000000000000000000000000000001": {
+                    "balance": "0x1",
+                    "movePrecompileToAddress": "0x1000000000000000000000000000000000000001"
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let account = &overrides[&Address::with_last_byte(1)];
+        assert_eq!(account.balance, Some(U256::from(1)));
+    }
+}
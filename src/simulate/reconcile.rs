@@ -0,0 +1,306 @@
+//! Reconciliation of everything a discovery found into one requirement per
+//! (account, asset, kind), run once after the simulation loop.
+
+use forge::revm::primitives::{Address, U256};
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::simulate::ledger::{Holding, Ledger};
+use crate::simulate::report::{DiscoveryReport, StepRequirement};
+use crate::simulate::types::{AssetSpec, MissingAssetInfo, RequirementKind};
+
+/// Identity of an asset regardless of amounts or ids, except that an
+/// ERC-1155 spec for a single id (as shortfalls are reported) is told apart
+/// from the token's other ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum AssetKey {
+    Native,
+    ERC20(Address),
+    ERC721(Address),
+    ERC1155(Address, Option<U256>),
+}
+
+impl AssetKey {
+    fn of(spec: &AssetSpec) -> Self {
+        match spec {
+            AssetSpec::Native(_) => AssetKey::Native,
+            AssetSpec::ERC20 { token, .. } => AssetKey::ERC20(*token),
+            AssetSpec::ERC721 { token, .. } => AssetKey::ERC721(*token),
+            AssetSpec::ERC1155 {
+                token,
+                token_amounts,
+            } => {
+                let id = match token_amounts.keys().collect::<Vec<_>>()[..] {
+                    [id] => Some(*id),
+                    _ => None,
+                };
+                AssetKey::ERC1155(*token, id)
+            }
+        }
+    }
+}
+
+/// Merge `findings` (in the order they were found) into one requirement per
+/// (account, asset, kind), sorted by that key.
+///
+/// Repeated findings don't add up: the same shortfall seen in a later round
+/// takes the larger amount, NFT ids are united and ERC-1155 ids take their
+/// per-id maximum. When the last run succeeded, its `ledger` raises fungible
+/// balance requirements to what actually left the account, which also
+/// covers several transfers that were each satisfiable on their own.
+pub(crate) fn reconcile(
+    findings: Vec<MissingAssetInfo>,
+    ledger: Option<&Ledger>,
+) -> Vec<MissingAssetInfo> {
+    let mut merged: BTreeMap<(Address, AssetKey, RequirementKind), MissingAssetInfo> =
+        BTreeMap::new();
+
+    for finding in findings {
+        match merged.entry(key_of(&finding)) {
+            Entry::Vacant(entry) => {
+                entry.insert(finding);
+            }
+            // The earliest finding saw the state before anything was dealt,
+            // so its balance and frame are kept
+            Entry::Occupied(mut entry) => absorb(entry.get_mut(), finding),
+        }
+    }
+
+    merged
+        .into_values()
+        .map(|mut requirement| {
+            if let Some(ledger) = ledger {
+                settle(&mut requirement, ledger);
+            }
+            requirement
+        })
+        .collect()
+}
+
+/// Whether `later` asks for anything (account, asset, kind) `earlier` doesn't.
+pub(crate) fn has_new_requirements(
+    earlier: &[MissingAssetInfo],
+    later: &[MissingAssetInfo],
+) -> bool {
+    let known: BTreeSet<_> = earlier.iter().map(key_of).collect();
+    later
+        .iter()
+        .any(|missing| !known.contains(&key_of(missing)))
+}
+
+fn key_of(finding: &MissingAssetInfo) -> (Address, AssetKey, RequirementKind) {
+    (
+        finding.account,
+        AssetKey::of(&finding.required),
+        finding.kind,
+    )
+}
+
+/// Requirements of a bundle's `steps`, each attributed to the first step
+/// that needed it. Later steps asking for the same thing only raise it.
+pub(crate) fn attribute(steps: &[DiscoveryReport]) -> Vec<StepRequirement> {
+    let mut merged: BTreeMap<(Address, AssetKey, RequirementKind), StepRequirement> =
+        BTreeMap::new();
+
+    for (step, report) in steps.iter().enumerate() {
+        for requirement in &report.requirements {
+            let key = (
+                requirement.account,
+                AssetKey::of(&requirement.required),
+                requirement.kind,
+            );
+            match merged.entry(key) {
+                Entry::Vacant(entry) => {
+                    entry.insert(StepRequirement {
+                        step,
+                        requirement: requirement.clone(),
+                    });
+                }
+                Entry::Occupied(mut entry) => {
+                    absorb(&mut entry.get_mut().requirement, requirement.clone())
+                }
+            }
+        }
+    }
+
+    let mut attributed: Vec<StepRequirement> = merged.into_values().collect();
+    attributed.sort_by_key(|attributed| attributed.step);
+    attributed
+}
+
+fn absorb(existing: &mut MissingAssetInfo, later: MissingAssetInfo) {
+    existing.missing_amount = existing.missing_amount.max(later.missing_amount);
+    existing.verified_minimum = existing.verified_minimum.max(later.verified_minimum);
+
+    match (&mut existing.required, later.required) {
+        (AssetSpec::Native(amount), AssetSpec::Native(more)) => *amount = (*amount).max(more),
+        (AssetSpec::ERC20 { amount, .. }, AssetSpec::ERC20 { amount: more, .. }) => {
+            *amount = (*amount).max(more)
+        }
+        (
+            AssetSpec::ERC721 { token_ids, .. },
+            AssetSpec::ERC721 {
+                token_ids: more, ..
+            },
+        ) => {
+            for id in more {
+                if !token_ids.contains(&id) {
+                    token_ids.push(id);
+                }
+            }
+        }
+        (
+            AssetSpec::ERC1155 { token_amounts, .. },
+            AssetSpec::ERC1155 {
+                token_amounts: more,
+                ..
+            },
+        ) => {
+            for (id, amount) in more {
+                let entry = token_amounts.entry(id).or_insert(U256::ZERO);
+                *entry = (*entry).max(amount);
+            }
+        }
+        _ => unreachable!("findings are grouped by asset"),
+    }
+    recompute_missing(existing);
+}
+
+// Balance requirements cover at least what the successful run moved out
+fn settle(requirement: &mut MissingAssetInfo, ledger: &Ledger) {
+    if requirement.kind != RequirementKind::Balance {
+        return;
+    }
+    let account = requirement.account;
+    match &mut requirement.required {
+        AssetSpec::Native(amount) => {
+            if let Some(net) = ledger.net_outflow(account, Holding::Native) {
+                *amount = (*amount).max(net);
+            }
+        }
+        AssetSpec::ERC20 { token, amount } => {
+            if let Some(net) = ledger.net_outflow(account, Holding::ERC20 { token: *token }) {
+                *amount = (*amount).max(net);
+            }
+        }
+        AssetSpec::ERC1155 {
+            token,
+            token_amounts,
+        } => {
+            for (id, amount) in token_amounts.iter_mut() {
+                let holding = Holding::ERC1155 {
+                    token: *token,
+                    id: *id,
+                };
+                if let Some(net) = ledger.net_outflow(account, holding) {
+                    *amount = (*amount).max(net);
+                }
+            }
+        }
+        AssetSpec::ERC721 { .. } => {}
+    }
+    recompute_missing(requirement);
+}
+
+// `current_balance` is the (summed) balance before anything was dealt
+fn recompute_missing(requirement: &mut MissingAssetInfo) {
+    let required = match &requirement.required {
+        AssetSpec::Native(amount) | AssetSpec::ERC20 { amount, .. } => *amount,
+        AssetSpec::ERC1155 { token_amounts, .. } => token_amounts.values().copied().sum(),
+        AssetSpec::ERC721 { token_ids, .. } => U256::from(token_ids.len()),
+    };
+    let missing = required.saturating_sub(requirement.current_balance);
+    requirement.missing_amount = requirement.missing_amount.max(missing);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn repeated_shortfall_is_not_double_counted() {
+        let account = Address::repeat_byte(1);
+        let token = Address::repeat_byte(9);
+        let finding = |amount: u64| {
+            MissingAssetInfo::new(
+                account,
+                AssetSpec::ERC20 {
+                    token,
+                    amount: U256::from(amount),
+                },
+                U256::from(10),
+                U256::from(amount - 10),
+            )
+        };
+
+        // Found once, then again with a higher amount after a deal
+        let reconciled = reconcile(vec![finding(100), finding(100), finding(150)], None);
+        assert_eq!(reconciled.len(), 1);
+        assert_eq!(
+            reconciled[0].required,
+            AssetSpec::ERC20 {
+                token,
+                amount: U256::from(150)
+            }
+        );
+        assert_eq!(reconciled[0].missing_amount, U256::from(140));
+    }
+
+    #[test]
+    fn erc1155_ids_are_kept_apart() {
+        let account = Address::repeat_byte(1);
+        let token = Address::repeat_byte(9);
+        let finding = |id: u64, amount: u64, balance: u64| {
+            MissingAssetInfo::new(
+                account,
+                AssetSpec::ERC1155 {
+                    token,
+                    token_amounts: HashMap::from([(U256::from(id), U256::from(amount))]),
+                },
+                U256::from(balance),
+                U256::from(amount - balance),
+            )
+        };
+
+        let reconciled = reconcile(vec![finding(2, 7, 0), finding(1, 5, 2)], None);
+        let missing: Vec<_> = reconciled
+            .iter()
+            .map(|missing| (missing.current_balance, missing.missing_amount))
+            .collect();
+        assert_eq!(
+            missing,
+            vec![(U256::from(2), U256::from(3)), (U256::ZERO, U256::from(7))]
+        );
+    }
+
+    #[test]
+    fn output_is_sorted_by_account_then_asset() {
+        let spec = |token: u8| AssetSpec::ERC20 {
+            token: Address::repeat_byte(token),
+            amount: U256::from(1),
+        };
+        let finding = |account: u8, token: u8| {
+            MissingAssetInfo::new(
+                Address::repeat_byte(account),
+                spec(token),
+                U256::ZERO,
+                U256::from(1),
+            )
+        };
+
+        let reconciled = reconcile(vec![finding(2, 1), finding(1, 3), finding(1, 2)], None);
+        let order: Vec<_> = reconciled
+            .iter()
+            .map(|missing| (missing.account, missing.required.clone()))
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                (Address::repeat_byte(1), spec(2)),
+                (Address::repeat_byte(1), spec(3)),
+                (Address::repeat_byte(2), spec(1)),
+            ]
+        );
+    }
+}
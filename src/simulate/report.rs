@@ -0,0 +1,74 @@
+use crate::simulate::blocker::BlockerCategory;
+use crate::simulate::ledger::Outflow;
+use crate::simulate::time_warp::TimeRequirement;
+use crate::simulate::types::MissingAssetInfo;
+
+/// Outcome of the last simulation run of a discovery.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiscoveryStatus {
+    /// The transaction succeeded, after the deals listed in the report.
+    Succeeded,
+    /// Still reverting, and nothing we could deal would change that.
+    Reverted,
+    /// Still turning up new shortfalls when `max_iterations` ran out.
+    MaxIterationsReached,
+}
+
+/// One simulation run of the discovery loop.
+#[derive(Debug, Clone, Default)]
+pub struct Iteration {
+    pub reverted: bool,               // Whether this run failed
+    pub gas_used: u64,                // Gas used by this run (0 if rejected before execution)
+    pub deals: Vec<MissingAssetInfo>, // Requirements dealt after this run
+}
+
+/// Everything a discovery found out about a transaction.
+#[derive(Debug, Clone)]
+pub struct DiscoveryReport {
+    pub status: DiscoveryStatus,
+    pub iterations: Vec<Iteration>,
+    pub revert_reason: Option<String>, // Decoded reason of the last run, if it failed
+    pub gas_used: u64,                 // Gas used by the last run
+    pub requirements: Vec<MissingAssetInfo>, // Aggregated findings, each linked to its frame
+    pub outflows: Vec<Outflow>,        // Measured movements (outflow mode, once the tx succeeded)
+    pub time_requirement: Option<TimeRequirement>, // Block that gets past a time gate (time warp only)
+    pub blocker: Option<BlockerCategory>,          // Why it still reverts with nothing left to fund
+}
+
+impl DiscoveryReport {
+    /// `true` if the transaction goes through once `requirements` are funded.
+    pub fn succeeded(&self) -> bool {
+        self.status == DiscoveryStatus::Succeeded
+    }
+
+    /// Requirements no deal will satisfy: shortfalls of accounts outside the
+    /// accounts of interest and rejected signatures.
+    pub fn blockers(&self) -> impl Iterator<Item = &MissingAssetInfo> {
+        self.requirements
+            .iter()
+            .filter(|missing| missing.external || !missing.kind.is_fundable())
+    }
+}
+
+/// A requirement of a bundle, attributed to the step that first needed it.
+#[derive(Debug, Clone)]
+pub struct StepRequirement {
+    pub step: usize, // Index of the call in the bundle
+    pub requirement: MissingAssetInfo,
+}
+
+/// Everything a discovery found out about an ordered list of calls.
+#[derive(Debug, Clone)]
+pub struct BundleReport {
+    pub steps: Vec<DiscoveryReport>, // One per call that ran; stops at the first failing one
+    pub requirements: Vec<StepRequirement>, // Sorted by step
+    pub funding: Vec<MissingAssetInfo>, // What each account needs before step one, for all steps
+}
+
+impl BundleReport {
+    /// `true` if every step went through once `funding` is provided. Steps
+    /// only stop early after a failing one, so the last step tells.
+    pub fn succeeded(&self) -> bool {
+        self.steps.last().is_none_or(DiscoveryReport::succeeded)
+    }
+}
@@ -0,0 +1,154 @@
+//! Time warp: find out whether a revert depends on the block timestamp or
+//! number (vesting cliffs, timelocks, expired deadlines, auction windows) by
+//! re-running the transaction in earlier and later blocks.
+
+use alloy_sol_types::{Revert, SolError};
+use forge::executors::Executor;
+use forge::revm::primitives::{Env, U256};
+
+use crate::simulate::asset_simulator::tx_env;
+use crate::simulate::types::Call;
+
+/// Largest distance probed in either direction: 2^32 seconds (or blocks).
+const MAX_WARP: u64 = 1 << 32;
+
+/// When a time-gated transaction gets past its gate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeRequirement {
+    /// Too early: gets through from this block timestamp on.
+    NotBefore { timestamp: u64 },
+    /// Too late: got through up to this block timestamp.
+    NotAfter { timestamp: u64 },
+    /// Too early: gets through from this block number on.
+    NotBeforeBlock { number: u64 },
+    /// Too late: got through up to this block number.
+    NotAfterBlock { number: u64 },
+}
+
+impl TimeRequirement {
+    /// Move `env` to the block this requirement asks for.
+    pub fn apply(&self, env: &mut Env) {
+        match *self {
+            TimeRequirement::NotBefore { timestamp } | TimeRequirement::NotAfter { timestamp } => {
+                env.block.timestamp = U256::from(timestamp)
+            }
+            TimeRequirement::NotBeforeBlock { number }
+            | TimeRequirement::NotAfterBlock { number } => env.block.number = U256::from(number),
+        }
+    }
+}
+
+/// Search for the block where `call` stops failing the way it fails now,
+/// timestamps first, then block numbers. Distances double until the outcome
+/// changes and the boundary is then bisected, so a window narrower than the
+/// step it falls into can be missed.
+pub(crate) fn search(executor: &Executor, call: &Call) -> Option<TimeRequirement> {
+    let now = outcome(executor, call, |_| {});
+    let timestamp = executor.env().block.timestamp.saturating_to::<u64>();
+    let number = executor.env().block.number.saturating_to::<u64>();
+
+    let at_timestamp = |timestamp: u64| {
+        outcome(executor, call, |env| {
+            env.block.timestamp = U256::from(timestamp)
+        }) != now
+    };
+    let at_number =
+        |number: u64| outcome(executor, call, |env| env.block.number = U256::from(number)) != now;
+
+    if let Some(timestamp) = boundary(timestamp, Direction::Later, &at_timestamp) {
+        return Some(TimeRequirement::NotBefore { timestamp });
+    }
+    if let Some(timestamp) = boundary(timestamp, Direction::Earlier, &at_timestamp) {
+        return Some(TimeRequirement::NotAfter { timestamp });
+    }
+    if let Some(number) = boundary(number, Direction::Later, &at_number) {
+        return Some(TimeRequirement::NotBeforeBlock { number });
+    }
+    if let Some(number) = boundary(number, Direction::Earlier, &at_number) {
+        return Some(TimeRequirement::NotAfterBlock { number });
+    }
+    None
+}
+
+#[derive(Clone, Copy)]
+enum Direction {
+    Later,
+    Earlier,
+}
+
+// The value closest to `now` for which `changed` holds
+fn boundary(now: u64, direction: Direction, changed: impl Fn(u64) -> bool) -> Option<u64> {
+    let mut unchanged = now;
+    let mut step = 1;
+    while step <= MAX_WARP {
+        let candidate = match direction {
+            Direction::Later => now.saturating_add(step),
+            Direction::Earlier => now.saturating_sub(step),
+        };
+        if candidate == unchanged {
+            break; // Ran into 0 or u64::MAX
+        }
+        if changed(candidate) {
+            return Some(bisect(unchanged, candidate, &changed));
+        }
+        unchanged = candidate;
+        step *= 2;
+    }
+    None
+}
+
+fn bisect(mut unchanged: u64, mut changed: u64, is_changed: impl Fn(u64) -> bool) -> u64 {
+    while unchanged.abs_diff(changed) > 1 {
+        let mid = unchanged.min(changed) + unchanged.abs_diff(changed) / 2;
+        if is_changed(mid) {
+            changed = mid;
+        } else {
+            unchanged = mid;
+        }
+    }
+    changed
+}
+
+// Whether the call reverts, with which error selector and, for an
+// `Error(string)`, which reason; `None` if it isn't executed. Other revert
+// data often carries the block itself (e.g. `Expired(deadline, now)`), so it
+// would change at every block.
+fn outcome(
+    executor: &Executor,
+    call: &Call,
+    warp: impl FnOnce(&mut Env),
+) -> Option<(bool, Option<[u8; 4]>, Option<String>)> {
+    let mut env = tx_env(executor, call);
+    warp(&mut env.env);
+    executor.call_with_env(env).ok().map(|result| {
+        if !result.reverted {
+            return (false, None, None);
+        }
+        let selector = result
+            .result
+            .get(..4)
+            .and_then(|selector| selector.try_into().ok());
+        let reason = Revert::abi_decode(&result.result)
+            .ok()
+            .map(|revert| revert.reason);
+        (true, selector, reason)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_closest_boundary() {
+        // Unlocks at 1_000_000, checked at 10
+        let unlocked = |timestamp: u64| timestamp >= 1_000_000;
+        assert_eq!(boundary(10, Direction::Later, unlocked), Some(1_000_000));
+        assert_eq!(boundary(10, Direction::Earlier, unlocked), None);
+
+        // Expired at 500, checked at 10_000
+        let open = |timestamp: u64| timestamp <= 500;
+        assert_eq!(boundary(10_000, Direction::Earlier, open), Some(500));
+        assert_eq!(boundary(10_000, Direction::Later, open), None);
+    }
+}
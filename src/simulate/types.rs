@@ -1,14 +1,24 @@
-use forge::revm::primitives::{Address, Bytes, U256};
-use forge::traces::CallTrace;
+use forge::revm::primitives::{AccessList, Address, Bytes, SignedAuthorization, U256};
+use forge::traces::{CallTrace, CallTraceNode};
 use std::collections::HashMap;
 
 use super::PotentialMissingAsset;
+use super::overrides::StateOverride;
 
+/// A transaction to simulate. Envelope fields left unset fall back to the
+/// executor's env.
+#[derive(Debug, Clone)]
 pub struct Call {
     pub from: Address,
-    pub to: Address,
+    pub to: Option<Address>, // `None` deploys `data` as init code
     pub value: U256,
     pub data: Bytes,
+    pub gas_limit: Option<u64>,
+    pub gas_price: Option<U256>, // Legacy gas price, or EIP-1559 max fee per gas
+    pub max_priority_fee_per_gas: Option<U256>, // EIP-1559 tip
+    pub nonce: Option<u64>,      // Must match the sender's nonce when set
+    pub access_list: AccessList, // EIP-2930
+    pub authorization_list: Vec<SignedAuthorization>, // EIP-7702
 }
 
 impl Call {
@@ -16,9 +26,109 @@ impl Call {
     pub fn new(from: Address, to: Address, data: impl Into<Bytes>, value: impl Into<U256>) -> Self {
         Self {
             from,
-            to,
+            to: Some(to),
             value: value.into(),
             data: data.into(),
+            gas_limit: None,
+            gas_price: None,
+            max_priority_fee_per_gas: None,
+            nonce: None,
+            access_list: AccessList::default(),
+            authorization_list: Vec::new(),
+        }
+    }
+
+    /// A contract creation transaction running `init_code`.
+    pub fn create(from: Address, init_code: impl Into<Bytes>, value: impl Into<U256>) -> Self {
+        Self {
+            to: None,
+            ..Self::new(from, Address::ZERO, init_code, value)
+        }
+    }
+
+    pub fn with_gas_limit(mut self, gas_limit: u64) -> Self {
+        self.gas_limit = Some(gas_limit);
+        self
+    }
+
+    /// Legacy (or EIP-2930) pricing.
+    pub fn with_gas_price(mut self, gas_price: U256) -> Self {
+        self.gas_price = Some(gas_price);
+        self
+    }
+
+    /// EIP-1559 pricing.
+    pub fn with_fees(mut self, max_fee_per_gas: U256, max_priority_fee_per_gas: U256) -> Self {
+        self.gas_price = Some(max_fee_per_gas);
+        self.max_priority_fee_per_gas = Some(max_priority_fee_per_gas);
+        self
+    }
+
+    pub fn with_nonce(mut self, nonce: u64) -> Self {
+        self.nonce = Some(nonce);
+        self
+    }
+
+    pub fn with_access_list(mut self, access_list: AccessList) -> Self {
+        self.access_list = access_list;
+        self
+    }
+
+    pub fn with_authorization_list(mut self, authorizations: Vec<SignedAuthorization>) -> Self {
+        self.authorization_list = authorizations;
+        self
+    }
+}
+
+/// How `check_transaction_with_options` finds what a transaction needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DiscoveryMode {
+    /// Follow reverts: deal what the failing frame asked for, one blocker per run.
+    #[default]
+    Revert,
+    /// Re-run the tx, generously funding whatever the checkers flag in any
+    /// frame of a failing run (not just the revert path), until it succeeds.
+    /// Requirements are then derived from the transfers it actually made,
+    /// plus the sender's gas fee, measured against the state before any
+    /// funding. Assets no checker flags are never funded. Once it succeeds,
+    /// only those requirements are dealt to the simulator's state before the
+    /// tx runs on it; the generous funding stays on a throwaway copy.
+    Outflow,
+}
+
+/// How much revert mode deals for a shortfall.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DealStrategy {
+    /// ERC-20 balances get `U256::MAX >> 1`; proves the tx *can* succeed.
+    #[default]
+    Generous,
+    /// Deal exactly the reported requirement. If the tx then still fails,
+    /// search for the smallest balance that lets it pass and report it as
+    /// `MissingAssetInfo::verified_minimum`.
+    Exact,
+}
+
+#[derive(Debug, Clone)]
+pub struct CheckOptions {
+    pub mode: DiscoveryMode,
+    pub auto_fix: bool, // Deal what's missing and re-run (revert mode only)
+    pub deal_strategy: DealStrategy, // Revert mode only
+    pub max_iterations: usize,
+    pub keep_state: bool, // Keep deals (and the executed tx) instead of reverting afterwards
+    pub state_override: StateOverride, // Applied before discovery, like `eth_call`'s
+    pub time_warp: bool, // Look for a block past time-gated reverts, see `DiscoveryReport::time_requirement`
+}
+
+impl Default for CheckOptions {
+    fn default() -> Self {
+        Self {
+            mode: DiscoveryMode::Revert,
+            auto_fix: true,
+            deal_strategy: DealStrategy::Generous,
+            max_iterations: 10,
+            keep_state: false,
+            state_override: StateOverride::new(),
+            time_warp: false,
         }
     }
 }
@@ -136,7 +246,7 @@ impl AssetGrant {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AssetType {
     Native,
     ERC20,
@@ -144,15 +254,124 @@ pub enum AssetType {
     ERC1155,
 }
 
+/// What exactly an account lacks for a given asset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub enum RequirementKind {
+    /// Not enough of a fungible balance.
+    #[default]
+    Balance,
+    /// Doesn't own the required token ids.
+    Ownership,
+    /// Holds the asset, but `operator` isn't approved to move it.
+    Approval { operator: Address },
+    /// ERC-20 allowance granted to `spender` is below the required amount.
+    Allowance { spender: Address },
+    /// An ERC-2612 `permit` for `spender` was rejected; no deal can fix it.
+    InvalidPermit {
+        spender: Address,
+        reason: PermitFailure,
+    },
+    /// An EIP-3009 transfer authorization was rejected; no deal can fix it.
+    InvalidAuthorization { reason: AuthorizationFailure },
+}
+
+impl RequirementKind {
+    /// Whether dealing the asset can satisfy this requirement. Findings that
+    /// aren't fundable are reported but never dealt.
+    pub fn is_fundable(&self) -> bool {
+        !matches!(
+            self,
+            RequirementKind::InvalidPermit { .. } | RequirementKind::InvalidAuthorization { .. }
+        )
+    }
+}
+
+/// Why an ERC-2612 `permit` was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PermitFailure {
+    /// `deadline` is before the block timestamp.
+    ExpiredDeadline,
+    /// Signed over a nonce other than the owner's current one.
+    NonceMismatch,
+    /// The signature doesn't recover to the owner for any nearby nonce.
+    InvalidSignature,
+}
+
+/// Why an EIP-3009 `transferWithAuthorization` / `receiveWithAuthorization`
+/// was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum AuthorizationFailure {
+    /// The authorization nonce was already used or canceled.
+    NonceUsed,
+    /// The block timestamp isn't past `validAfter` yet.
+    NotYetValid,
+    /// The block timestamp is past `validBefore`.
+    Expired,
+    /// `receiveWithAuthorization` called by someone other than the payee.
+    CallerNotPayee,
+    /// The signature doesn't recover to `from`.
+    InvalidSignature,
+}
+
 #[derive(Debug, Clone)]
 pub struct MissingAssetInfo {
     pub account: Address,
-    pub required: AssetSpec,   // What asset/amount is needed
-    pub current_balance: U256, // Current balance (for reporting)
-    pub missing_amount: U256,  // How much is missing (for reporting)
+    pub required: AssetSpec,            // What asset/amount is needed
+    pub kind: RequirementKind,          // Why the account falls short
+    pub current_balance: U256,          // Current balance (for reporting)
+    pub missing_amount: U256,           // How much is missing (for reporting)
+    pub external: bool,                 // Account is outside the accounts of interest (never dealt)
+    pub frame: Option<TraceFrame>,      // Trace frame on the revert path that produced this
+    pub verified_minimum: Option<U256>, // Smallest balance a re-run confirmed is enough
+    pub deployer: Option<Address>,      // Sender deploying `account`, if this is in its constructor
+}
+
+impl MissingAssetInfo {
+    /// A plain balance shortfall; the simulator fills in the remaining fields.
+    pub fn new(
+        account: Address,
+        required: AssetSpec,
+        current_balance: U256,
+        missing_amount: U256,
+    ) -> Self {
+        Self {
+            account,
+            required,
+            kind: RequirementKind::Balance,
+            current_balance,
+            missing_amount,
+            external: false,
+            frame: None,
+            verified_minimum: None,
+            deployer: None,
+        }
+    }
+
+    pub fn with_kind(mut self, kind: RequirementKind) -> Self {
+        self.kind = kind;
+        self
+    }
+}
+
+/// Location of a frame in the trace arena of the simulated transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceFrame {
+    pub index: usize, // Node index in the trace arena
+    pub depth: usize, // Call depth (0 = top-level call)
+    pub address: Address,
+}
+
+impl TraceFrame {
+    pub fn from_node(node: &CallTraceNode) -> Self {
+        Self {
+            index: node.idx,
+            depth: node.trace.depth,
+            address: node.trace.address,
+        }
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct AssetContext {
     pub potential_asset: PotentialMissingAsset,
     pub trace: CallTrace,
@@ -179,6 +398,45 @@ impl AssetContext {
         storage_slots
     }
 
+    /// Context for dealing a grant outside of any call: the recipient moves
+    /// its own assets, so no operator approval is set up.
+    pub fn for_grant(grant: &AssetGrant) -> Self {
+        let (token_address, required_amount, token_ids, token_amounts) = match &grant.asset {
+            AssetSpec::Native(amount) => (Address::ZERO, *amount, Vec::new(), HashMap::new()),
+            AssetSpec::ERC20 { token, amount } => (*token, *amount, Vec::new(), HashMap::new()),
+            AssetSpec::ERC721 { token, token_ids } => (
+                *token,
+                U256::from(token_ids.len()),
+                token_ids.clone(),
+                HashMap::new(),
+            ),
+            AssetSpec::ERC1155 {
+                token,
+                token_amounts,
+            } => (
+                *token,
+                token_amounts.values().copied().sum(),
+                Vec::new(),
+                token_amounts.clone(),
+            ),
+        };
+
+        Self {
+            potential_asset: PotentialMissingAsset {
+                asset_type: grant.asset_type(),
+                token_address,
+                account: grant.recipient,
+                required_amount,
+                spender: grant.recipient,
+                token_ids,
+                token_amounts,
+                calldata: Bytes::new(),
+            },
+            trace: CallTrace::default(),
+            storage_accesses: Vec::new(),
+        }
+    }
+
     /// Create AssetContext from trace and potential asset
     pub fn from_trace(potential_asset: PotentialMissingAsset, trace: CallTrace) -> Self {
         let storage_accesses = Self::extract_storage_accesses(&trace);
@@ -195,12 +453,12 @@ impl AssetContext {
 #[cfg(test)]
 impl Default for Call {
     fn default() -> Self {
-        Self {
-            from: Address::random(),
-            to: Address::random(),
-            value: U256::ZERO,
-            data: Bytes::default(),
-        }
+        Self::new(
+            Address::random(),
+            Address::random(),
+            Bytes::default(),
+            U256::ZERO,
+        )
     }
 }
 
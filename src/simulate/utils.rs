@@ -1,28 +1,135 @@
-use forge::traces::{CallKind, CallTrace, SparsedTraceArena};
-
-// Simplified function that returns only the last relevant trace
-pub fn find_last_non_proxy_call(traces: &SparsedTraceArena) -> Option<&CallTrace> {
-    // Convert to a vector for easier iteration from the end
-    let trace_list: Vec<&CallTrace> = traces.nodes().iter().map(|node| &node.trace).collect();
-
-    // Use iterator methods for a more idiomatic approach
-    trace_list
-        .iter()
-        .rev()
-        .find(|trace| {
-            // If it's not a delegate call, it's definitely not a proxy
-            if trace.kind != CallKind::DelegateCall {
-                return true;
-            }
-
-            // For delegate calls, check if it's a pure proxy by comparing with previous trace
-            let trace_idx = trace_list.iter().position(|t| t == *trace).unwrap();
-            if trace_idx == 0 {
-                return true; // First trace can't be a proxy of a previous one
-            }
-
-            // If calldata doesn't match exactly, it's not a pure proxy
-            trace.data != trace_list[trace_idx - 1].data
-        })
-        .copied()
+use alloy_sol_types::{SolCall, sol};
+use forge::executors::{Executor, RawCallResult};
+use forge::revm::primitives::{Address, U256, address};
+use forge::traces::{CallKind, CallTraceNode, SparsedTraceArena};
+
+sol! {
+    function supportsInterface(bytes4 interfaceId) external view returns (bool);
+}
+
+/// Node indices from the root down to the deepest reverting frame.
+///
+/// Empty when the top-level call succeeded. At every level we descend into
+/// the last failing child, since that is the revert that bubbled up.
+pub fn revert_path(traces: &SparsedTraceArena) -> Vec<usize> {
+    let nodes = traces.nodes();
+    let mut path = Vec::new();
+
+    let mut current = match nodes.first() {
+        Some(root) if !root.trace.success => root,
+        _ => return path,
+    };
+
+    loop {
+        path.push(current.idx);
+        match current
+            .children
+            .iter()
+            .rev()
+            .map(|&child| &nodes[child])
+            .find(|child| !child.trace.success)
+        {
+            Some(child) => current = child,
+            None => break,
+        }
+    }
+
+    path
+}
+
+/// Frames on the revert path that checkers should look at, root first.
+/// Pure proxy forwards (delegate calls repeating the parent's calldata) are
+/// skipped so every logical call is only inspected once.
+pub fn candidate_frames(traces: &SparsedTraceArena) -> Vec<&CallTraceNode> {
+    let nodes = traces.nodes();
+    revert_path(traces)
+        .into_iter()
+        .map(|idx| &nodes[idx])
+        .filter(|node| !is_proxy_forward(nodes, node))
+        .collect()
+}
+
+fn is_proxy_forward(nodes: &[CallTraceNode], node: &CallTraceNode) -> bool {
+    node.trace.kind == CallKind::DelegateCall
+        && node
+            .parent
+            .is_some_and(|parent| nodes[parent].trace.data == node.trace.data)
+}
+
+/// Whether `node` runs inside `account`'s own constructor, i.e. `account` is
+/// being created by a CREATE/CREATE2 frame at or above `node`.
+pub fn in_constructor_of(nodes: &[CallTraceNode], node: &CallTraceNode, account: Address) -> bool {
+    let mut current = Some(node);
+    while let Some(node) = current {
+        if matches!(node.trace.kind, CallKind::Create | CallKind::Create2)
+            && node.trace.address == account
+        {
+            return true;
+        }
+        current = node.parent.map(|parent| &nodes[parent]);
+    }
+    false
+}
+
+/// Run a read-only call against `target` and decode its return value.
+/// `None` if the call reverts or returns something undecodable.
+pub fn view_call<C: SolCall>(executor: &Executor, target: Address, call: &C) -> Option<C::Return> {
+    // Use the zero-address as sender so accounts with code don't interfere
+    let result = executor
+        .call_raw(Address::ZERO, target, call.abi_encode().into(), U256::ZERO)
+        .ok()?;
+    if result.reverted {
+        return None;
+    }
+    C::abi_decode_returns(&result.result).ok()
+}
+
+/// ERC-165 probe; anything that reverts or answers garbage is "unsupported".
+pub fn supports_interface(executor: &Executor, target: Address, interface_id: [u8; 4]) -> bool {
+    view_call(
+        executor,
+        target,
+        &supportsInterfaceCall {
+            interfaceId: interface_id.into(),
+        },
+    )
+    .unwrap_or(false)
+}
+
+/// Execute and commit a call as `from` (impersonation), leaving the account's
+/// nonce untouched so the simulated transaction itself isn't affected.
+pub fn transact_as(
+    executor: &mut Executor,
+    from: Address,
+    to: Address,
+    calldata: Vec<u8>,
+) -> Result<RawCallResult, eyre::Error> {
+    let nonce = executor.get_nonce(from)?;
+    let result = executor.transact_raw(from, to, calldata.into(), U256::ZERO)?;
+    executor.set_nonce(from, nonce)?;
+    Ok(result)
+}
+
+/// Recover the signer of `digest` through the ecrecover precompile, so no
+/// secp256k1 implementation is needed here. `None` for invalid signatures.
+pub fn ecrecover(
+    executor: &Executor,
+    digest: [u8; 32],
+    v: u8,
+    r: [u8; 32],
+    s: [u8; 32],
+) -> Option<Address> {
+    const ECRECOVER: Address = address!("0000000000000000000000000000000000000001");
+
+    let mut input = Vec::with_capacity(128);
+    input.extend_from_slice(&digest);
+    input.extend_from_slice(&U256::from(v).to_be_bytes::<32>());
+    input.extend_from_slice(&r);
+    input.extend_from_slice(&s);
+
+    let result = executor
+        .call_raw(Address::ZERO, ECRECOVER, input.into(), U256::ZERO)
+        .ok()?;
+    // The precompile returns nothing (rather than reverting) on failure
+    (result.result.len() == 32).then(|| Address::from_slice(&result.result[12..]))
 }